@@ -0,0 +1,115 @@
+//! PNG sprite sheet export of the built-in font, for reuse in other tools
+
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::font::{BUILTIN_FONT, LETTER_DATA};
+
+/// Glyphs per row of the atlas
+pub const ATLAS_COLUMNS: usize = 16;
+
+/// Where one glyph sits in the atlas, in atlas pixels
+#[derive(Debug, Clone, Serialize)]
+pub struct AtlasEntry {
+    pub char: char,
+    pub x: u32,
+    pub y: u32,
+}
+
+/// Sidecar metadata written next to the atlas image
+#[derive(Debug, Clone, Serialize)]
+pub struct AtlasLayout {
+    /// Size of every cell in atlas pixels
+    pub cell_width: u32,
+    pub cell_height: u32,
+    pub scale: u32,
+    pub columns: usize,
+    /// One entry per glyph, in `LETTER_DATA` order
+    pub glyphs: Vec<AtlasEntry>,
+}
+
+/// Lays out every glyph of the built-in font at `scale`, one per cell in `LETTER_DATA`
+/// order, `ATLAS_COLUMNS` to a row
+pub fn atlas_layout(scale: u32) -> AtlasLayout {
+    let cell_width = BUILTIN_FONT.cell_width as u32 * scale;
+    let cell_height = BUILTIN_FONT.cell_height as u32 * scale;
+    let glyphs = LETTER_DATA
+        .iter()
+        .enumerate()
+        .map(|(i, &byte)| AtlasEntry {
+            char: byte as char,
+            x: (i % ATLAS_COLUMNS) as u32 * cell_width,
+            y: (i / ATLAS_COLUMNS) as u32 * cell_height,
+        })
+        .collect();
+    AtlasLayout { cell_width, cell_height, scale, columns: ATLAS_COLUMNS, glyphs }
+}
+
+impl AtlasLayout {
+    /// Size of the whole atlas image in pixels
+    pub fn size(&self) -> (u32, u32) {
+        let rows = self.glyphs.len().div_ceil(self.columns);
+        (self.columns as u32 * self.cell_width, rows as u32 * self.cell_height)
+    }
+}
+
+/// Renders the atlas as RGBA8: white glyph pixels on a transparent background.
+/// Returns the layout along with the pixels.
+pub fn render_font_atlas(scale: u32) -> (AtlasLayout, Vec<u8>) {
+    let layout = atlas_layout(scale);
+    let (width, height) = layout.size();
+    let mut rgba = vec![0; (width * height * 4) as usize];
+    let cell_width = BUILTIN_FONT.cell_width;
+    for (entry, &byte) in layout.glyphs.iter().zip(&LETTER_DATA) {
+        let Some(glyph) = BUILTIN_FONT.glyph(char::from(byte)) else {
+            continue;
+        };
+        for (i, _) in glyph.iter().enumerate().filter(|&(_, &pixel)| pixel) {
+            let (col, row) = ((i % cell_width) as u32, (i / cell_width) as u32);
+            for dy in 0..scale {
+                for dx in 0..scale {
+                    let x = entry.x + col * scale + dx;
+                    let y = entry.y + row * scale + dy;
+                    let idx = ((y * width + x) * 4) as usize;
+                    rgba[idx..idx + 4].copy_from_slice(&[0xFF; 4]);
+                }
+            }
+        }
+    }
+    (layout, rgba)
+}
+
+/// Writes the font atlas at `scale` to `path` as a PNG, and its layout to the same path
+/// with a `.json` extension
+pub fn export_font_atlas(path: &Path, scale: u32) -> io::Result<()> {
+    let (layout, rgba) = render_font_atlas(scale);
+    let (width, height) = layout.size();
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&rgba)?;
+
+    let metadata = serde_json::to_string_pretty(&layout).map_err(io::Error::other)?;
+    std::fs::write(path.with_extension("json"), metadata)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::font::{GLYPH_HEIGHT, GLYPH_WIDTH};
+
+    #[test]
+    fn atlas_size_matches_glyph_count_and_cell_size() {
+        let (layout, rgba) = render_font_atlas(2);
+        assert_eq!(layout.glyphs.len(), LETTER_DATA.len());
+        assert_eq!((layout.cell_width, layout.cell_height), (2 * GLYPH_WIDTH as u32, 2 * GLYPH_HEIGHT as u32));
+        let rows = LETTER_DATA.len().div_ceil(ATLAS_COLUMNS) as u32;
+        assert_eq!(layout.size(), (ATLAS_COLUMNS as u32 * 10, rows * 16));
+        assert_eq!(rgba.len(), (layout.size().0 * layout.size().1 * 4) as usize);
+        let last = layout.glyphs.last().unwrap();
+        assert_eq!((last.x, last.y), (((LETTER_DATA.len() - 1) % ATLAS_COLUMNS) as u32 * 10, (rows - 1) * 16));
+    }
+}
@@ -0,0 +1,60 @@
+//! Headless timing guard for the text hot path
+//!
+//! Expanding glyphs into scaled pixel blocks happens for every label, readout and the
+//! hidden text on every frame, so a font change that makes it much slower shows up as
+//! dropped frames long before anyone profiles it. The guard is a test that only runs in
+//! optimized builds, so CI runs `cargo test --release` as well as the usual debug tests.
+
+use std::time::{Duration, Instant};
+
+use crate::font::BUILTIN_FONT;
+use crate::render::BASE_TEXT_SCALE;
+use crate::text::{TextStyle, draw_str, measure_str};
+
+/// A 28 character run, about as long as the longest readouts
+const SAMPLE: &str = "Window 1920x1080 at (12, 34)";
+
+/// How many times the sample is drawn per measurement
+pub const ITERATIONS: u32 = 10_000;
+
+/// Longest a measurement may take in a release build, which needs well under a tenth of
+/// this. The slack is deliberate so slow or busy CI machines don't fail the job, while a
+/// change that makes expansion an order of magnitude slower still does. Debug builds are
+/// too slow for the budget to mean anything.
+pub const BUDGET: Duration = Duration::from_secs(2);
+
+/// Draws `SAMPLE` at the base text scale `ITERATIONS` times into a buffer that fits it
+/// and returns how long that took, or an error if it went over `BUDGET`
+pub fn bench_text_expansion() -> Result<Duration, String> {
+    let font = &*BUILTIN_FONT;
+    let style = TextStyle { scale: BASE_TEXT_SCALE, ..TextStyle::default() };
+    let (width, height) = measure_str(font, SAMPLE, &style);
+    let width = width as u32;
+    let mut buffer = vec![0; (width * height as u32) as usize];
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        draw_str(&mut buffer, font, std::hint::black_box(SAMPLE), 0, 0, width, &style);
+    }
+    let elapsed = start.elapsed();
+    std::hint::black_box(&buffer);
+
+    if elapsed <= BUDGET {
+        Ok(elapsed)
+    } else {
+        Err(format!("{ITERATIONS} draws of a {} character run took {elapsed:?}, over the {BUDGET:?} budget", SAMPLE.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg_attr(debug_assertions, ignore = "the budget is for optimized builds; run with --release")]
+    fn text_expansion_is_within_budget() {
+        if let Err(err) = bench_text_expansion() {
+            panic!("{err}");
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! A blocky, QR-code-like presentation of the hidden text, for `--block-code`
+//!
+//! Not a real QR code: each byte becomes eight modules, most significant bit first,
+//! laid out row by row in the smallest square that holds them all. Set bits are dark.
+
+use crate::geometry::Rect;
+use crate::render::fill_rect;
+
+/// Light modules left around the grid, as with a QR code's quiet zone
+pub const QUIET_ZONE: i32 = 1;
+
+/// The modules for some bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockCode {
+    /// Modules per row
+    pub columns: usize,
+    pub rows: usize,
+    /// Row-major, `true` for dark
+    pub modules: Vec<bool>,
+}
+
+impl BlockCode {
+    /// Encodes `bytes`; modules past the last bit are light
+    pub fn encode(bytes: &[u8]) -> Self {
+        let bits = bytes.len() * 8;
+        let columns = bits.isqrt() + usize::from(bits.isqrt().pow(2) < bits);
+        let rows = if columns == 0 { 0 } else { bits.div_ceil(columns) };
+        let mut modules = vec![false; columns * rows];
+        for (i, module) in modules.iter_mut().take(bits).enumerate() {
+            *module = bytes[i / 8] & (0x80 >> (i % 8)) != 0;
+        }
+        Self { columns, rows, modules }
+    }
+
+    /// Largest whole-pixel module size at which the grid and its quiet zone fit in
+    /// `width` x `height`, at least 1
+    pub fn module_size(&self, width: u32, height: u32) -> i32 {
+        let across = (self.columns as i32 + 2 * QUIET_ZONE).max(1);
+        let down = (self.rows as i32 + 2 * QUIET_ZONE).max(1);
+        (width as i32 / across).min(height as i32 / down).max(1)
+    }
+
+    /// Draws the grid with its top-left quiet-zone corner at `(x, y)`, with modules as big
+    /// as fit the buffer. Only the first `shown` bytes' modules are drawn dark, so a
+    /// reveal fills the grid in without it changing size.
+    pub fn draw(&self, buffer: &mut [u32], width: u32, height: u32, (x, y): (i32, i32), shown: usize, (light, dark): (u32, u32)) {
+        let module = self.module_size(width, height);
+        let panel = Rect::new(
+            x,
+            y,
+            (self.columns as i32 + 2 * QUIET_ZONE) * module,
+            (self.rows as i32 + 2 * QUIET_ZONE) * module,
+        );
+        fill_rect(buffer, width, height, panel, light);
+        for (i, _) in self.modules.iter().enumerate().take(shown * 8).filter(|(_, dark)| **dark) {
+            let column = (i % self.columns) as i32 + QUIET_ZONE;
+            let row = (i / self.columns) as i32 + QUIET_ZONE;
+            fill_rect(buffer, width, height, Rect::new(x + column * module, y + row * module, module, module), dark);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_holds_eight_modules_per_byte() {
+        for (len, columns, rows) in [(0, 0, 0), (1, 3, 3), (2, 4, 4), (5, 7, 6), (32, 16, 16)] {
+            let code = BlockCode::encode(&vec![0xFF; len]);
+            assert_eq!((code.columns, code.rows), (columns, rows), "{len} bytes");
+            assert_eq!(code.modules.len(), columns * rows);
+            // Every bit is a dark module, and the padding after them is light
+            assert_eq!(code.modules.iter().filter(|&&dark| dark).count(), len * 8);
+        }
+        // Most significant bit first
+        let code = BlockCode::encode(&[0b1010_0000, 0b0000_0001]);
+        assert_eq!(&code.modules[..8], [true, false, true, false, false, false, false, false]);
+        assert!(code.modules[15]);
+        // 4 columns plus the quiet zone on both sides in 60 pixels
+        assert_eq!(code.module_size(60, 200), 10);
+    }
+}
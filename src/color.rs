@@ -0,0 +1,140 @@
+//! Helpers for working with packed `0xAARRGGBB` pixels
+
+use std::sync::LazyLock;
+
+/// Number of steps in the linear-light lookup; finer than 8 bits so dark blends don't band
+const LINEAR_STEPS: usize = 4096;
+
+struct SrgbTables {
+    /// sRGB byte -> linear value in `0..LINEAR_STEPS`
+    to_linear: [u16; 256],
+    /// linear value -> sRGB byte
+    to_srgb: [u8; LINEAR_STEPS],
+}
+
+static SRGB_TABLES: LazyLock<SrgbTables> = LazyLock::new(|| {
+    let max = (LINEAR_STEPS - 1) as f32;
+    let mut to_linear = [0; 256];
+    for (i, entry) in to_linear.iter_mut().enumerate() {
+        let c = i as f32 / 255.0;
+        let linear = if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) };
+        *entry = (linear * max).round() as u16;
+    }
+    let mut to_srgb = [0; LINEAR_STEPS];
+    for (i, entry) in to_srgb.iter_mut().enumerate() {
+        let l = i as f32 / max;
+        let c = if l <= 0.0031308 { l * 12.92 } else { 1.055 * l.powf(1.0 / 2.4) - 0.055 };
+        *entry = (c * 255.0).round() as u8;
+    }
+    SrgbTables { to_linear, to_srgb }
+});
+
+fn channel(color: u32, shift: u32) -> u32 {
+    (color >> shift) & 0xFF
+}
+
+/// Parses an opaque `RRGGBB` color, with or without a leading `#`
+pub fn parse_color(s: &str) -> Option<u32> {
+    let hex = s.trim().trim_start_matches('#');
+    if hex.len() != 6 {
+        return None;
+    }
+    u32::from_str_radix(hex, 16).ok().map(|color| 0xFF000000 | color)
+}
+
+/// Formats a color as `#RRGGBB`, dropping alpha; the inverse of `parse_color`
+pub fn format_color(color: u32) -> String {
+    format!("#{:06X}", color & 0xFFFFFF)
+}
+
+/// The bytes of `color` in RGBA order, as image files and raw video expect
+pub const fn argb_to_rgba(color: u32) -> [u8; 4] {
+    let [a, r, g, b] = color.to_be_bytes();
+    [r, g, b, a]
+}
+
+/// Perceived brightness of `color` (0-255) using Rec. 709 weights on the sRGB bytes
+pub fn luminance(color: u32) -> u8 {
+    let weighted = 2126 * channel(color, 16) + 7152 * channel(color, 8) + 722 * channel(color, 0);
+    (weighted / 10_000) as u8
+}
+
+/// Black or white, whichever reads better over `background`
+pub fn contrasting(background: u32) -> u32 {
+    if luminance(background) >= 128 { 0xFF000000 } else { 0xFFFFFFFF }
+}
+
+/// Linearly interpolates each channel from `from` (t = 0) to `to` (t = 1); the result is
+/// opaque
+pub fn lerp_color(from: u32, to: u32, t: f32) -> u32 {
+    lerp_color_dithered(from, to, t, 0.0)
+}
+
+/// `lerp_color` with `offset` (in `-0.5..0.5`) added to each channel before rounding, so
+/// neighbouring pixels given different offsets round to different bytes
+pub fn lerp_color_dithered(from: u32, to: u32, t: f32, offset: f32) -> u32 {
+    let t = t.clamp(0.0, 1.0);
+    let mut out = 0xFF000000;
+    for shift in [16, 8, 0] {
+        let a = channel(from, shift) as f32;
+        let b = channel(to, shift) as f32;
+        out |= ((a + (b - a) * t + offset).round().clamp(0.0, 255.0) as u32) << shift;
+    }
+    out
+}
+
+/// 4x4 Bayer matrix: the order in which pixels of a 4x4 tile switch on
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Ordered dithering offset for `lerp_color_dithered` at pixel `(x, y)`, in `-0.5..0.5`.
+///
+/// Deterministic and tiled every 4 pixels, so anchoring it to world coordinates keeps the
+/// pattern still while the window moves.
+pub fn bayer_offset(x: i64, y: i64) -> f32 {
+    let threshold = BAYER_4X4[y.rem_euclid(4) as usize][x.rem_euclid(4) as usize];
+    (threshold as f32 + 0.5) / 16.0 - 0.5
+}
+
+/// Multiplies each channel by `factor` (clamped to 0..=1), keeping the color opaque
+pub fn scale_brightness(color: u32, factor: f32) -> u32 {
+    lerp_color(0xFF000000, color, factor)
+}
+
+/// Blends `src` over `dst` with `alpha` (0 = all `dst`, 255 = all `src`).
+///
+/// With `gamma_correct` the channels are mixed in linear light rather than directly on
+/// the sRGB bytes, which keeps soft edges from looking too dark. The result is opaque.
+pub fn blend_pixel(dst: u32, src: u32, alpha: u8, gamma_correct: bool) -> u32 {
+    let a = alpha as u32;
+    let mut out = 0xFF000000;
+    for shift in [16, 8, 0] {
+        let d = channel(dst, shift);
+        let s = channel(src, shift);
+        let mixed = if gamma_correct {
+            let tables = &*SRGB_TABLES;
+            let dl = tables.to_linear[d as usize] as u32;
+            let sl = tables.to_linear[s as usize] as u32;
+            let l = (sl * a + dl * (255 - a) + 127) / 255;
+            tables.to_srgb[l as usize] as u32
+        } else {
+            (s * a + d * (255 - a) + 127) / 255
+        };
+        out |= mixed << shift;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gamma_correct_half_blend_is_brighter() {
+        let naive = blend_pixel(0xFF000000, 0xFFFFFFFF, 128, false);
+        let linear = blend_pixel(0xFF000000, 0xFFFFFFFF, 128, true);
+        assert_eq!(naive, 0xFF808080);
+        assert!(luminance(linear) > luminance(naive), "{linear:08X} should be brighter than {naive:08X}");
+        // Linear 0.5 is about 188 in sRGB
+        assert!((185..=190).contains(&channel(linear, 16)), "{linear:08X}");
+    }
+}
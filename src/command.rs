@@ -0,0 +1,75 @@
+//! Commands that drive a running window from outside the event loop
+//!
+//! With `--commands` the binary reads one command per line from stdin on a separate
+//! thread and forwards each to the event loop, so a script or another process can
+//! control the window:
+//!
+//! ```text
+//! redraw
+//! text Hello from outside
+//! text
+//! ```
+//!
+//! `text` with nothing after it clears the text again.
+
+/// A request from another thread or process
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Draw a new frame, e.g. after something the window can't see has changed
+    Redraw,
+    /// Replace the command text shown in the window; empty hides it
+    SetText(String),
+}
+
+impl Command {
+    /// Applies the command to the window's command text. Every command also needs a
+    /// redraw, which is up to the caller.
+    pub fn apply(self, command_text: &mut Option<String>) {
+        match self {
+            Command::Redraw => {}
+            Command::SetText(text) => *command_text = (!text.is_empty()).then_some(text),
+        }
+    }
+}
+
+/// Parses one line of command input, `None` if it isn't a known command. Surrounding
+/// whitespace is ignored, except inside the `text` argument after the first space.
+pub fn parse_command(line: &str) -> Option<Command> {
+    let line = line.trim_end_matches(['\r', '\n']).trim_start();
+    let (name, argument) = line.split_once(' ').unwrap_or((line, ""));
+    match name.trim_end() {
+        "redraw" => Some(Command::Redraw),
+        "text" => Some(Command::SetText(argument.to_string())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn set_text_from_another_thread_updates_the_text() {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in ["text Hello  there", "redraw", "bogus", "text"] {
+                if let Some(command) = parse_command(line) {
+                    sender.send(command).unwrap();
+                }
+            }
+        });
+        let mut text = None;
+        let mut seen = Vec::new();
+        for command in receiver {
+            seen.push(command.clone());
+            command.apply(&mut text);
+            if seen.len() == 2 {
+                // After "redraw", the text from the first command is still there
+                assert_eq!(text.as_deref(), Some("Hello  there"));
+            }
+        }
+        assert_eq!(seen.len(), 3);
+        assert_eq!(text, None);
+    }
+}
@@ -0,0 +1,162 @@
+//! Saving and loading the live settings as TOML
+//!
+//! A saved file looks like
+//!
+//! ```toml
+//! boundary_size = 100
+//! boundary_mode = "flat"
+//! origin = "top-left"
+//! background = "#000000"
+//! boundary_color = "#00FF00"
+//! text_color = "#FFFFFF"
+//! corner_radius = 0
+//! show_compass = false
+//! show_magnifier = false
+//! band_labels = false
+//! ```
+//!
+//! Every key is optional when loading; missing ones keep their defaults. Modes, origins
+//! and colors use the same spellings as the command line.
+
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::color::{format_color, parse_color};
+use crate::geometry::Origin;
+use crate::render::{BoundaryMode, RenderOptions, clamp_boundary_size};
+
+/// The settings that can be changed while running, as saved to and loaded from TOML
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub boundary_size: i32,
+    #[serde(serialize_with = "serialize_mode", deserialize_with = "deserialize_mode")]
+    pub boundary_mode: BoundaryMode,
+    #[serde(serialize_with = "serialize_origin", deserialize_with = "deserialize_origin")]
+    pub origin: Origin,
+    #[serde(serialize_with = "serialize_color", deserialize_with = "deserialize_color")]
+    pub background: u32,
+    #[serde(serialize_with = "serialize_color", deserialize_with = "deserialize_color")]
+    pub boundary_color: u32,
+    #[serde(serialize_with = "serialize_color", deserialize_with = "deserialize_color")]
+    pub text_color: u32,
+    pub corner_radius: u32,
+    pub show_compass: bool,
+    pub show_magnifier: bool,
+    pub band_labels: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::from_options(&RenderOptions::default())
+    }
+}
+
+impl Config {
+    /// The current values of the saved settings in `options`
+    pub fn from_options(options: &RenderOptions) -> Self {
+        Self {
+            boundary_size: options.boundary_size,
+            boundary_mode: options.boundary_mode,
+            origin: options.origin,
+            background: options.background,
+            boundary_color: options.boundary_color,
+            text_color: options.text.color,
+            corner_radius: options.corner_radius,
+            show_compass: options.show_compass,
+            show_magnifier: options.show_magnifier,
+            band_labels: options.band_labels,
+        }
+    }
+
+    /// Copies every saved setting into `options`
+    pub fn apply(&self, options: &mut RenderOptions) {
+        options.boundary_size = clamp_boundary_size(self.boundary_size);
+        options.boundary_mode = self.boundary_mode;
+        options.origin = self.origin;
+        options.background = self.background;
+        options.boundary_color = self.boundary_color;
+        options.text.color = self.text_color;
+        options.corner_radius = self.corner_radius;
+        options.show_compass = self.show_compass;
+        options.show_magnifier = self.show_magnifier;
+        options.band_labels = self.band_labels;
+    }
+
+    pub fn to_toml(&self) -> String {
+        // Every field is a plain value, so serializing can't fail
+        toml::to_string(self).expect("config serializes to TOML")
+    }
+
+    /// Parses a config, describing the first problem on failure
+    pub fn from_toml(s: &str) -> Result<Self, String> {
+        toml::from_str(s).map_err(|err| err.to_string())
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::from_toml(&std::fs::read_to_string(path)?).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        std::fs::write(path, self.to_toml())
+    }
+}
+
+fn serialize_color<S: Serializer>(color: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format_color(*color))
+}
+
+fn deserialize_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    parse_color(&s).ok_or_else(|| serde::de::Error::custom(format!("expected a #RRGGBB color, got {s:?}")))
+}
+
+fn serialize_mode<S: Serializer>(mode: &BoundaryMode, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(mode.name())
+}
+
+fn deserialize_mode<'de, D: Deserializer<'de>>(deserializer: D) -> Result<BoundaryMode, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    BoundaryMode::parse(&s).ok_or_else(|| serde::de::Error::custom(format!("expected flat, proximity, gradient or edge-only, got {s:?}")))
+}
+
+fn serialize_origin<S: Serializer>(origin: &Origin, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(origin.name())
+}
+
+fn deserialize_origin<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Origin, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    Origin::parse(&s).ok_or_else(|| serde::de::Error::custom(format!("expected top-left or bottom-left, got {s:?}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_modify_save_reload_keeps_the_values() {
+        let loaded = Config::from_toml("boundary_size = 80\nboundary_mode = \"gradient\"\nbackground = \"#101010\"\n").unwrap();
+        assert_eq!((loaded.boundary_size, loaded.boundary_mode, loaded.background), (80, BoundaryMode::Gradient, 0xFF101010));
+        assert_eq!(loaded.boundary_color, Config::default().boundary_color);
+
+        // Tweak a few settings live, as the hotkeys would
+        let mut options = RenderOptions::default();
+        loaded.apply(&mut options);
+        options.boundary_size = 150;
+        options.boundary_color = 0xFFFFB000;
+        options.origin = Origin::BottomLeft;
+        let saved = Config::from_options(&options);
+
+        let path = std::env::temp_dir().join(format!("window-messing-config-test-{}.toml", std::process::id()));
+        saved.save(&path).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        let reloaded = Config::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(text.contains("boundary_color = \"#FFB000\""), "{text}");
+        assert_eq!(reloaded.unwrap(), saved);
+        assert_eq!((saved.boundary_size, saved.boundary_mode, saved.background), (150, BoundaryMode::Gradient, 0xFF101010));
+    }
+}
@@ -0,0 +1,68 @@
+//! Contact sheet export: the window's view from a grid of positions, in one PNG
+//!
+//! The grid spans everything worth looking at: the monitor plus the hidden text above
+//! it. `CONTACT_GRID` x `CONTACT_GRID` windows, each a `1 / CONTACT_TILE_DIVISOR` of the
+//! monitor in each direction, are spread evenly over that area (the corner tiles touch
+//! its corners) and laid out in the same order, separated by `CONTACT_GUTTER` pixels.
+//! For a 1920x1080 monitor that is 64 tiles of 480x270 in a 3854x2174 image.
+
+use std::io;
+use std::path::Path;
+
+use crate::geometry::Rect;
+use crate::render::{BASE_TEXT_SCALE, RenderOptions, hidden_text_rect, render_frame};
+use crate::snapshot::write_png;
+
+/// Tiles along each side of the sheet
+pub const CONTACT_GRID: u32 = 8;
+/// Each tile is the monitor size divided by this
+pub const CONTACT_TILE_DIVISOR: u32 = 4;
+/// Gap between tiles
+pub const CONTACT_GUTTER: u32 = 2;
+/// Color of the gaps, distinct from every built-in background
+pub const CONTACT_GUTTER_COLOR: u32 = 0xFF808080;
+
+/// World area the sheet covers: the monitor and the hidden text `options` selects
+pub fn contact_area(monitor_size: (u32, u32), options: &RenderOptions) -> Rect {
+    let monitor = Rect::new(0, 0, monitor_size.0 as i32, monitor_size.1 as i32);
+    let text = hidden_text_rect(monitor_size, options.hidden_text().source.len(), BASE_TEXT_SCALE);
+    let (x, y) = (monitor.x.min(text.x), monitor.y.min(text.y));
+    Rect::new(x, y, monitor.right().max(text.right()) - x, monitor.bottom().max(text.bottom()) - y)
+}
+
+/// Top-left of the window for tile `index` of `CONTACT_GRID` along one axis, from
+/// `start` for the first tile to `start + span - window` for the last
+pub fn contact_position(index: u32, start: i32, span: i32, window: u32) -> i32 {
+    let travel = (span as i64 - window as i64).max(0);
+    start + (travel * index as i64 / (CONTACT_GRID - 1) as i64) as i32
+}
+
+/// Renders the contact sheet for `monitor_size`, returning its size and pixels
+pub fn render_contact_sheet(monitor_size: (u32, u32), options: &RenderOptions) -> (u32, u32, Vec<u32>) {
+    let tile = ((monitor_size.0 / CONTACT_TILE_DIVISOR).max(1), (monitor_size.1 / CONTACT_TILE_DIVISOR).max(1));
+    let width = CONTACT_GRID * tile.0 + (CONTACT_GRID - 1) * CONTACT_GUTTER;
+    let height = CONTACT_GRID * tile.1 + (CONTACT_GRID - 1) * CONTACT_GUTTER;
+    let area = contact_area(monitor_size, options);
+    let mut sheet = vec![CONTACT_GUTTER_COLOR; (width * height) as usize];
+    let mut buffer = vec![0; (tile.0 * tile.1) as usize];
+    for row in 0..CONTACT_GRID {
+        for column in 0..CONTACT_GRID {
+            let x = contact_position(column, area.x, area.width, tile.0);
+            let y = contact_position(row, area.y, area.height, tile.1);
+            render_frame(&mut buffer, tile.0, tile.1, (x, y), monitor_size, options);
+            let left = column * (tile.0 + CONTACT_GUTTER);
+            let top = row * (tile.1 + CONTACT_GUTTER);
+            for (ty, line) in buffer.chunks_exact(tile.0 as usize).enumerate() {
+                let start = ((top + ty as u32) * width + left) as usize;
+                sheet[start..start + line.len()].copy_from_slice(line);
+            }
+        }
+    }
+    (width, height, sheet)
+}
+
+/// Renders the contact sheet with the default look and writes it to `path` as a PNG
+pub fn export_contact_sheet(path: &Path, monitor_size: (u32, u32)) -> io::Result<()> {
+    let (width, height, pixels) = render_contact_sheet(monitor_size, &RenderOptions::default());
+    write_png(path, width, height, &pixels)
+}
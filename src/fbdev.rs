@@ -0,0 +1,209 @@
+//! Output to a Linux framebuffer device such as `/dev/fb0`, for kiosks without X11 or
+//! Wayland
+//!
+//! The device's geometry and pixel layout come from the `FBIOGET_VSCREENINFO` and
+//! `FBIOGET_FSCREENINFO` ioctls; frames are converted to that layout and copied into
+//! the memory-mapped framebuffer row by row, honouring its stride.
+
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::fd::AsRawFd;
+use std::path::Path;
+
+use memmap2::{MmapMut, MmapOptions};
+
+const FBIOGET_VSCREENINFO: libc::c_ulong = 0x4600;
+const FBIOGET_FSCREENINFO: libc::c_ulong = 0x4602;
+
+/// `struct fb_bitfield`
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+struct FbBitfield {
+    offset: u32,
+    length: u32,
+    msb_right: u32,
+}
+
+/// `struct fb_var_screeninfo`
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+struct FbVarScreeninfo {
+    xres: u32,
+    yres: u32,
+    xres_virtual: u32,
+    yres_virtual: u32,
+    xoffset: u32,
+    yoffset: u32,
+    bits_per_pixel: u32,
+    grayscale: u32,
+    red: FbBitfield,
+    green: FbBitfield,
+    blue: FbBitfield,
+    transp: FbBitfield,
+    nonstd: u32,
+    activate: u32,
+    height: u32,
+    width: u32,
+    accel_flags: u32,
+    pixclock: u32,
+    left_margin: u32,
+    right_margin: u32,
+    upper_margin: u32,
+    lower_margin: u32,
+    hsync_len: u32,
+    vsync_len: u32,
+    sync: u32,
+    vmode: u32,
+    rotate: u32,
+    colorspace: u32,
+    reserved: [u32; 4],
+}
+
+/// `struct fb_fix_screeninfo`
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy)]
+struct FbFixScreeninfo {
+    id: [u8; 16],
+    smem_start: libc::c_ulong,
+    smem_len: u32,
+    type_: u32,
+    type_aux: u32,
+    visual: u32,
+    xpanstep: u16,
+    ypanstep: u16,
+    ywrapstep: u16,
+    line_length: u32,
+    mmio_start: libc::c_ulong,
+    mmio_len: u32,
+    accel: u32,
+    capabilities: u16,
+    reserved: [u16; 2],
+}
+
+/// Where one color channel sits in a device pixel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Channel {
+    /// Bit offset from the least significant bit
+    pub offset: u32,
+    /// Width in bits, at most 8
+    pub length: u32,
+}
+
+impl Channel {
+    /// Places the top `length` bits of an 8-bit channel value
+    const fn encode(self, value: u32) -> u32 {
+        if self.length == 0 {
+            return 0;
+        }
+        (value >> (8 - self.length)) << self.offset
+    }
+}
+
+/// A device pixel layout, stored little-endian
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelFormat {
+    /// 2, 3 or 4
+    pub bytes_per_pixel: usize,
+    pub red: Channel,
+    pub green: Channel,
+    pub blue: Channel,
+    /// Alpha, if the device has any; always written opaque
+    pub alpha: Channel,
+}
+
+impl PixelFormat {
+    /// The device value for a `0xAARRGGBB` color
+    pub const fn encode(&self, color: u32) -> u32 {
+        self.red.encode((color >> 16) & 0xFF)
+            | self.green.encode((color >> 8) & 0xFF)
+            | self.blue.encode(color & 0xFF)
+            | self.alpha.encode(0xFF)
+    }
+}
+
+/// Converts `pixels` (`width` wide, `0xAARRGGBB`) into `dst`, a device buffer whose rows
+/// are `stride` bytes apart. Anything that doesn't fit in `dst` is cropped.
+pub fn convert_frame(dst: &mut [u8], stride: usize, format: &PixelFormat, pixels: &[u32], width: u32) {
+    let bpp = format.bytes_per_pixel;
+    let row_pixels = (stride / bpp).min(width as usize);
+    for (src_row, dst_row) in pixels.chunks_exact(width as usize).zip(dst.chunks_mut(stride)) {
+        for (&color, out) in src_row[..row_pixels].iter().zip(dst_row.chunks_exact_mut(bpp)) {
+            out.copy_from_slice(&format.encode(color).to_le_bytes()[..bpp]);
+        }
+    }
+}
+
+/// An open, memory-mapped framebuffer device
+#[derive(Debug)]
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    /// Bytes from one row to the next
+    stride: usize,
+    /// Byte offset of the visible area within the mapping
+    visible_offset: usize,
+    format: PixelFormat,
+    map: MmapMut,
+    _file: File,
+}
+
+impl Framebuffer {
+    /// Opens the device at `path` and maps its memory. Errors name the device and, for
+    /// permissions, the usual fix.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path).map_err(|err| match err.kind() {
+            io::ErrorKind::PermissionDenied => io::Error::new(
+                err.kind(),
+                format!("permission denied opening {}; run as root or join the group that owns it (usually video)", path.display()),
+            ),
+            _ => io::Error::new(err.kind(), format!("can't open {}: {err}", path.display())),
+        })?;
+        let mut var = FbVarScreeninfo::default();
+        let mut fix = FbFixScreeninfo::default();
+        // SAFETY: both ioctls fill in a struct of exactly the type passed, and the file
+        // descriptor is open for the duration of the calls
+        unsafe {
+            if libc::ioctl(file.as_raw_fd(), FBIOGET_VSCREENINFO as _, &mut var) < 0
+                || libc::ioctl(file.as_raw_fd(), FBIOGET_FSCREENINFO as _, &mut fix) < 0
+            {
+                let err = io::Error::last_os_error();
+                return Err(io::Error::new(err.kind(), format!("{} isn't a framebuffer device: {err}", path.display())));
+            }
+        }
+        let bytes_per_pixel = match var.bits_per_pixel {
+            16 | 24 | 32 => var.bits_per_pixel as usize / 8,
+            bits => {
+                return Err(io::Error::new(io::ErrorKind::Unsupported, format!("{bits} bits per pixel isn't supported; need 16, 24 or 32")));
+            }
+        };
+        let channel = |field: FbBitfield| Channel { offset: field.offset, length: field.length.min(8) };
+        let format = PixelFormat {
+            bytes_per_pixel,
+            red: channel(var.red),
+            green: channel(var.green),
+            blue: channel(var.blue),
+            alpha: channel(var.transp),
+        };
+        // SAFETY: the mapping is only written through `present`; other processes drawing
+        // to the same device can at worst garble the picture
+        let map = unsafe { MmapOptions::new().len(fix.smem_len as usize).map_mut(&file)? };
+        let stride = fix.line_length as usize;
+        let visible_offset = var.yoffset as usize * stride + var.xoffset as usize * bytes_per_pixel;
+        Ok(Self { width: var.xres, height: var.yres, stride, visible_offset, format, map, _file: file })
+    }
+
+    /// Visible resolution in pixels
+    pub const fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub const fn format(&self) -> &PixelFormat {
+        &self.format
+    }
+
+    /// Copies a frame (`width` wide, `0xAARRGGBB`) to the screen, anchored top-left
+    pub fn present(&mut self, pixels: &[u32], width: u32) {
+        let visible = &mut self.map[self.visible_offset..];
+        convert_frame(visible, self.stride, &self.format, pixels, width);
+    }
+}
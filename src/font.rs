@@ -0,0 +1,1041 @@
+//! The built-in 5x8 bitmap font and the baked hidden text, all evaluated at compile time
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// Columns in a glyph
+pub const GLYPH_WIDTH: usize = 5;
+/// Rows in a glyph, including the descender row
+pub const GLYPH_HEIGHT: usize = 8;
+/// Fixed distance from one glyph cell to the next: the glyph plus 1 column of spacing
+pub const GLYPH_ADVANCE: usize = GLYPH_WIDTH + 1;
+
+/// One glyph, row-major
+pub type Glyph = [[bool; GLYPH_WIDTH]; GLYPH_HEIGHT];
+
+/// A bitmap font loaded at runtime, whose cells can be any size
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Font {
+    pub cell_width: usize,
+    pub cell_height: usize,
+    /// Blank columns between cells, on top of `cell_width`
+    pub spacing: usize,
+    /// `cell_width * cell_height` pixels per glyph, row-major, keyed by the character
+    /// they draw
+    pub glyphs: HashMap<char, Vec<bool>>,
+    /// Built once for the cell size, so drawing missing characters doesn't allocate
+    tofu: Vec<bool>,
+}
+
+impl Font {
+    /// A font of `cell_width` x `cell_height` cells, `spacing` blank columns apart
+    pub fn new(cell_width: usize, cell_height: usize, spacing: usize, glyphs: HashMap<char, Vec<bool>>) -> Self {
+        let (width, height) = (cell_width, cell_height.saturating_sub(1).max(1));
+        let tofu = (0..cell_width * cell_height)
+            .map(|i| {
+                let (row, col) = (i / cell_width, i % cell_width);
+                row < height && (row == 0 || row == height - 1 || col == 0 || col == width - 1)
+            })
+            .collect();
+        Self { cell_width, cell_height, spacing, glyphs, tofu }
+    }
+
+    /// The built-in 5x8 font from `LETTER_DATA` and `FONT_DATA`, plus `LATIN1_GLYPHS`
+    pub fn builtin() -> Self {
+        let glyphs = LETTER_DATA
+            .iter()
+            .map(|&byte| char::from(byte))
+            .zip(&FONT_DATA)
+            .chain(LATIN1_GLYPHS.iter().map(|(c, glyph)| (*c, glyph)))
+            .map(|(c, glyph)| (c, glyph.as_flattened().to_vec()))
+            .collect();
+        Self::new(GLYPH_WIDTH, GLYPH_HEIGHT, GLYPH_ADVANCE - GLYPH_WIDTH, glyphs)
+    }
+
+    pub fn glyph(&self, c: char) -> Option<&[bool]> {
+        self.glyphs.get(&c).map(Vec::as_slice)
+    }
+
+    /// The "tofu" box drawn for characters without a glyph: the outline of the cell
+    /// above its bottom row, which is left for descenders
+    pub fn tofu(&self) -> &[bool] {
+        &self.tofu
+    }
+
+    /// Distance from one cell to the next in font pixels
+    pub fn advance(&self) -> usize {
+        self.cell_width + self.spacing
+    }
+}
+
+/// `Font::builtin`, built on first use
+pub static BUILTIN_FONT: LazyLock<Font> = LazyLock::new(Font::builtin);
+
+pub const fn string_to_bytes<const N:usize>(s: &str) -> Option<[u8; N]> {
+    if s.len() == N {
+        let mut i = 0;
+        let mut out: [u8; N] = [0; N];
+        while i < N {
+            out[i] = s.as_bytes()[i];
+            i += 1;
+        }
+        Some(out)
+    } else {
+        None
+    }
+}
+
+// Each CTF instance bakes one flag, picked with a `flag-*` feature; `flag-a` is a default
+#[cfg(not(any(feature = "flag-a", feature = "flag-b", feature = "flag-c")))]
+compile_error!("enable one of the `flag-a`, `flag-b` and `flag-c` features");
+#[cfg(any(
+    all(feature = "flag-a", feature = "flag-b"),
+    all(feature = "flag-a", feature = "flag-c"),
+    all(feature = "flag-b", feature = "flag-c"),
+))]
+compile_error!("enable only one of the `flag-a`, `flag-b` and `flag-c` features");
+
+#[cfg(feature = "flag-a")]
+pub const FLAG: &str = "ictf{Teeheehee_you_found_me}";
+#[cfg(feature = "flag-b")]
+pub const FLAG: &str = "ictf{Peekaboo_I_see_you}";
+#[cfg(feature = "flag-c")]
+pub const FLAG: &str = "ictf{Hiding_in_plain_sight}";
+
+pub const LEN: usize = FLAG.len();
+pub const TEXT_SOURCE: [u8;LEN] = string_to_bytes(FLAG).unwrap();
+
+pub const fn text_to_bitmap<const N:usize>(text: &[u8;N]) -> Option<[Glyph; N]> {
+    let mut result = [[[false; GLYPH_WIDTH]; GLYPH_HEIGHT]; N];
+    let mut i = 0;
+    while i < N {
+        let char = text[i];
+        if let Some(char_index) = index_u8(&LETTER_DATA, char) {
+            result[i] = FONT_DATA[char_index];
+        } else {
+            return None; // Invalid character
+        }
+        i+= 1;
+    }
+    Some(result)
+}
+
+pub const fn index_u8<const N: usize>(arr: &[u8; N], element: u8) -> Option<usize> {
+    let l = arr.len();
+    let mut i = 0;
+    while i < l {
+        if arr[i] == element {
+            return Some(i);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Looks up the glyph for `byte` at runtime, for text that isn't baked at compile time
+pub const fn glyph_for(byte: u8) -> Option<&'static Glyph> {
+    match index_u8(&LETTER_DATA, byte) {
+        Some(index) => Some(&FONT_DATA[index]),
+        None => None,
+    }
+}
+
+/// The byte whose built-in glyph is exactly `glyph`, the reverse of `glyph_for`. The
+/// first match wins if two bytes share a glyph.
+pub fn glyph_to_byte(glyph: &Glyph) -> Option<u8> {
+    FONT_DATA.iter().position(|candidate| candidate == glyph).map(|index| LETTER_DATA[index])
+}
+
+/// Advance adjustments in font pixels for glyph pairs that look too loose in
+/// proportional mode
+pub const KERNING: &[((u8, u8), i32)] = &[
+    ((b'A', b'V'), -1),
+    ((b'V', b'A'), -1),
+    ((b'A', b'W'), -1),
+    ((b'W', b'A'), -1),
+    ((b'T', b'o'), -1),
+    ((b'T', b'e'), -1),
+    ((b'T', b'a'), -1),
+    ((b'L', b'T'), -1),
+    ((b'Y', b'o'), -1),
+    ((b'F', b'o'), -1),
+];
+
+// Fails the build, rather than panicking in `unwrap`, if the selected flag can't be drawn
+const _: () = assert!(
+    text_to_bitmap(&TEXT_SOURCE).is_some(),
+    "the selected flag contains a character missing from LETTER_DATA",
+);
+
+pub const TEXT_BITMAPS: [Glyph; LEN] = text_to_bitmap(&TEXT_SOURCE).unwrap();
+
+/// A string baked into glyphs at compile time, like `TEXT_SOURCE`/`TEXT_BITMAPS`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BakedText {
+    pub source: &'static [u8],
+    pub bitmaps: &'static [Glyph],
+}
+
+/// Bakes a string literal into a `BakedText`, failing the build if any character is
+/// missing from `LETTER_DATA`
+macro_rules! bake {
+    ($text:expr) => {{
+        const SOURCE: [u8; $text.len()] = string_to_bytes($text).unwrap();
+        const BITMAPS: [Glyph; $text.len()] = text_to_bitmap(&SOURCE).unwrap();
+        BakedText { source: &SOURCE, bitmaps: &BITMAPS }
+    }};
+}
+
+/// Strings the hidden text can show, picked at runtime with the number keys. The flag
+/// comes first; the rest are decoys no longer than it, so the flag's `hidden_text_rect`
+/// covers them all.
+pub const BAKED_TEXTS: [BakedText; 3] = [
+    BakedText { source: &TEXT_SOURCE, bitmaps: &TEXT_BITMAPS },
+    bake!("Nothing_to_see_here"),
+    bake!("Not_this_one_either"),
+];
+
+pub const LETTER_DATA: [u8; 26 + 26 + 4 + 10 + 7 + 3] = [
+    // Uppercase letters A-Z
+    b'A', b'B', b'C', b'D', b'E', b'F', b'G', b'H', b'I', b'J', b'K', b'L', b'M',
+    b'N', b'O', b'P', b'Q', b'R', b'S', b'T', b'U', b'V', b'W', b'X', b'Y', b'Z',
+    // Lowercase letters a-z
+    b'a', b'b', b'c', b'd', b'e', b'f', b'g', b'h', b'i', b'j', b'k', b'l', b'm',
+    b'n', b'o', b'p', b'q', b'r', b's', b't', b'u', b'v', b'w', b'x', b'y', b'z',
+    // Special characters: space, underscore, curly braces
+    b'{', b'}', b'_', b' ',
+    // Digits 0-9
+    b'0', b'1', b'2', b'3', b'4', b'5', b'6', b'7', b'8', b'9',
+    // Punctuation for numeric readouts
+    b'-', b'.', b',', b':', b'(', b')', b'/',
+    // Symbols for the key bindings overlay
+    b'+', b'=', b'?',
+];
+
+/// Accented Latin-1 letters the built-in font has on top of `LETTER_DATA`, for names in
+/// runtime text; the hidden text stays ASCII
+pub const LATIN1_GLYPHS: [(char, Glyph); 3] = [
+    ('é', [
+        [false, false, false, true, false],
+        [false, false, true, false, false],
+        [false, true, true, true, false],
+        [true, false, false, false, true],
+        [true, true, true, true, true],
+        [true, false, false, false, false],
+        [false, true, true, true, false],
+        [false, false, false, false, false],
+    ]),
+    ('ñ', [
+        [false, true, true, false, true],
+        [true, false, true, true, false],
+        [true, true, true, true, false],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [false, false, false, false, false],
+    ]),
+    ('ü', [
+        [false, false, false, false, false],
+        [false, true, false, true, false],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [false, true, true, true, true],
+        [false, false, false, false, false],
+    ]),
+];
+
+// Checks the character tables when the crate builds: a repeated `LETTER_DATA` byte would
+// leave the glyph paired with its second copy unreachable, and an extra glyph for an
+// ASCII character would silently replace the `FONT_DATA` one in `Font::builtin`
+const _: () = {
+    let mut i = 0;
+    while i < LETTER_DATA.len() {
+        assert!(LETTER_DATA[i] >= b' ' && LETTER_DATA[i] <= b'~', "LETTER_DATA must be printable ASCII");
+        let mut j = i + 1;
+        while j < LETTER_DATA.len() {
+            assert!(LETTER_DATA[i] != LETTER_DATA[j], "LETTER_DATA has a repeated byte");
+            j += 1;
+        }
+        i += 1;
+    }
+    let mut i = 0;
+    while i < LATIN1_GLYPHS.len() {
+        assert!(!LATIN1_GLYPHS[i].0.is_ascii(), "LATIN1_GLYPHS must not redefine ASCII characters");
+        i += 1;
+    }
+};
+
+pub const FONT_DATA: [Glyph; LETTER_DATA.len()] = [
+    // Uppercase letters
+    [
+        [false, true, true, true, false],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, true, true, true, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [false, false, false, false, false],
+    ],
+    [
+        [true, true, true, true, false],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, true, true, true, false],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, true, true, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, true, true, true, false],
+        [true, false, false, false, true],
+        [true, false, false, false, false],
+        [true, false, false, false, false],
+        [true, false, false, false, false],
+        [true, false, false, false, true],
+        [false, true, true, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [true, true, true, false, false],
+        [true, false, false, true, false],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, true, false],
+        [true, true, true, false, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [true, true, true, true, true],
+        [true, false, false, false, false],
+        [true, false, false, false, false],
+        [true, true, true, true, false],
+        [true, false, false, false, false],
+        [true, false, false, false, false],
+        [true, true, true, true, true],
+        [false, false, false, false, false],
+    ],
+    [
+        [true, true, true, true, true],
+        [true, false, false, false, false],
+        [true, false, false, false, false],
+        [true, true, true, true, false],
+        [true, false, false, false, false],
+        [true, false, false, false, false],
+        [true, false, false, false, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, true, true, true, false],
+        [true, false, false, false, true],
+        [true, false, false, false, false],
+        [true, false, true, true, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [false, true, true, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, true, true, true, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, true, true, true, false],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, true, true, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, false, true],
+        [false, false, false, false, true],
+        [false, false, false, false, true],
+        [false, false, false, false, true],
+        [false, false, false, false, true],
+        [true, false, false, false, true],
+        [false, true, true, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [true, false, false, false, true],
+        [true, false, false, true, false],
+        [true, false, true, false, false],
+        [true, true, false, false, false],
+        [true, false, true, false, false],
+        [true, false, false, true, false],
+        [true, false, false, false, true],
+        [false, false, false, false, false],
+    ],
+    [
+        [true, false, false, false, false],
+        [true, false, false, false, false],
+        [true, false, false, false, false],
+        [true, false, false, false, false],
+        [true, false, false, false, false],
+        [true, false, false, false, false],
+        [true, true, true, true, true],
+        [false, false, false, false, false],
+    ],
+    [
+        [true, false, false, false, true],
+        [true, true, false, true, true],
+        [true, false, true, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [false, false, false, false, false],
+    ],
+    [
+        [true, false, false, false, true],
+        [true, true, false, false, true],
+        [true, false, true, false, true],
+        [true, false, false, true, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, true, true, true, false],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [false, true, true, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [true, true, true, true, false],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, true, true, true, false],
+        [true, false, false, false, false],
+        [true, false, false, false, false],
+        [true, false, false, false, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, true, true, true, false],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, true, false, true],
+        [true, false, false, true, false],
+        [false, true, true, false, true],
+        [false, false, false, false, false],
+    ],
+    [
+        [true, true, true, true, false],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, true, true, true, false],
+        [true, false, true, false, false],
+        [true, false, false, true, false],
+        [true, false, false, false, true],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, true, true, true, false],
+        [true, false, false, false, true],
+        [true, false, false, false, false],
+        [false, true, true, true, false],
+        [false, false, false, false, true],
+        [true, false, false, false, true],
+        [false, true, true, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [true, true, true, true, true],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [false, true, true, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [false, true, false, true, false],
+        [false, true, false, true, false],
+        [false, false, true, false, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, true, false, true],
+        [true, false, true, false, true],
+        [true, true, false, true, true],
+        [true, false, false, false, true],
+        [false, false, false, false, false],
+    ],
+    [
+        [true, false, false, false, true],
+        [false, true, false, true, false],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, true, false, true, false],
+        [true, false, false, false, true],
+        [false, false, false, false, false],
+    ],
+    [
+        [true, false, false, false, true],
+        [false, true, false, true, false],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [true, true, true, true, true],
+        [false, false, false, false, true],
+        [false, false, false, true, false],
+        [false, false, true, false, false],
+        [false, true, false, false, false],
+        [true, false, false, false, false],
+        [true, true, true, true, true],
+        [false, false, false, false, false],
+    ],
+    
+    // Lowercase letters  
+    [
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, true, true, true, false],
+        [false, false, false, false, true],
+        [false, true, true, true, true],
+        [true, false, false, false, true],
+        [false, true, true, true, true],
+        [false, false, false, false, false],
+    ],
+    [
+        [true, false, false, false, false],
+        [true, false, false, false, false],
+        [true, true, true, true, false],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, true, true, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, true, true, true, false],
+        [true, false, false, false, true],
+        [true, false, false, false, false],
+        [true, false, false, false, true],
+        [false, true, true, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, false, true],
+        [false, false, false, false, true],
+        [false, true, true, true, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [false, true, true, true, true],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, true, true, true, false],
+        [true, false, false, false, true],
+        [true, true, true, true, true],
+        [true, false, false, false, false],
+        [false, true, true, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, true, true, false],
+        [false, true, false, false, true],
+        [false, true, false, false, false],
+        [true, true, true, false, false],
+        [false, true, false, false, false],
+        [false, true, false, false, false],
+        [false, true, false, false, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, true, true, true, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [false, true, true, true, true],
+        [false, false, false, false, true],
+        [false, true, true, true, false],
+    ],
+    [
+        [true, false, false, false, false],
+        [true, false, false, false, false],
+        [true, true, true, true, false],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, true, false, false],
+        [false, false, false, false, false],
+        [false, true, true, false, false],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, true, true, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, true, false],
+        [false, false, false, false, false],
+        [false, false, true, true, false],
+        [false, false, false, true, false],
+        [false, false, false, true, false],
+        [false, false, false, true, false],
+        [true, false, false, true, false],
+        [false, true, true, false, false],
+    ],
+    [
+        [true, false, false, false, false],
+        [true, false, false, false, false],
+        [true, false, false, true, false],
+        [true, false, true, false, false],
+        [true, true, false, false, false],
+        [true, false, true, false, false],
+        [true, false, false, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, true, true, false, false],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, true, true, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [true, true, false, true, false],
+        [true, false, true, false, true],
+        [true, false, true, false, true],
+        [true, false, true, false, true],
+        [true, false, true, false, true],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [true, true, true, true, false],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, true, true, true, false],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [false, true, true, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [true, true, true, true, false],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, true, true, true, false],
+        [true, false, false, false, false],
+        [true, false, false, false, false],
+    ],
+    [
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, true, true, true, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [false, true, true, true, true],
+        [false, false, false, false, true],
+        [false, false, false, false, true],
+    ],
+    [
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [true, false, true, true, false],
+        [true, true, false, false, true],
+        [true, false, false, false, false],
+        [true, false, false, false, false],
+        [true, false, false, false, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, true, true, true, false],
+        [true, false, false, false, false],
+        [false, true, true, true, false],
+        [false, false, false, false, true],
+        [true, true, true, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, true, false, false, false],
+        [false, true, false, false, false],
+        [true, true, true, false, false],
+        [false, true, false, false, false],
+        [false, true, false, false, false],
+        [false, true, false, false, true],
+        [false, false, true, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [false, true, true, true, true],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [false, true, false, true, false],
+        [false, true, false, true, false],
+        [false, false, true, false, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [true, false, false, false, true],
+        [true, false, true, false, true],
+        [true, false, true, false, true],
+        [true, false, true, false, true],
+        [false, true, false, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [true, false, false, false, true],
+        [false, true, false, true, false],
+        [false, false, true, false, false],
+        [false, true, false, true, false],
+        [true, false, false, false, true],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [false, true, true, true, true],
+        [false, false, false, false, true],
+        [false, true, true, true, false],
+    ],
+    [
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [true, true, true, true, true],
+        [false, false, false, true, false],
+        [false, false, true, false, false],
+        [false, true, false, false, false],
+        [true, true, true, true, true],
+        [false, false, false, false, false],
+    ],
+    
+    // Special characters
+    [
+        [false, false, true, true, false],
+        [false, true, false, false, false],
+        [false, true, false, false, false],
+        [true, false, false, false, false],
+        [false, true, false, false, false],
+        [false, true, false, false, false],
+        [false, false, true, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, true, true, false, false],
+        [false, false, false, true, false],
+        [false, false, false, true, false],
+        [false, false, false, false, true],
+        [false, false, false, true, false],
+        [false, false, false, true, false],
+        [false, true, true, false, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [true, true, true, true, true],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+    ],
+    
+    // Digits
+    [
+        [false, true, true, true, false],
+        [true, false, false, false, true],
+        [true, false, false, true, true],
+        [true, false, true, false, true],
+        [true, true, false, false, true],
+        [true, false, false, false, true],
+        [false, true, true, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, true, false, false],
+        [false, true, true, false, false],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, true, true, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, true, true, true, false],
+        [true, false, false, false, true],
+        [false, false, false, false, true],
+        [false, false, false, true, false],
+        [false, false, true, false, false],
+        [false, true, false, false, false],
+        [true, true, true, true, true],
+        [false, false, false, false, false],
+    ],
+    [
+        [true, true, true, true, true],
+        [false, false, false, true, false],
+        [false, false, true, false, false],
+        [false, false, false, true, false],
+        [false, false, false, false, true],
+        [true, false, false, false, true],
+        [false, true, true, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, true, false],
+        [false, false, true, true, false],
+        [false, true, false, true, false],
+        [true, false, false, true, false],
+        [true, true, true, true, true],
+        [false, false, false, true, false],
+        [false, false, false, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [true, true, true, true, true],
+        [true, false, false, false, false],
+        [true, true, true, true, false],
+        [false, false, false, false, true],
+        [false, false, false, false, true],
+        [true, false, false, false, true],
+        [false, true, true, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, true, true, false],
+        [false, true, false, false, false],
+        [true, false, false, false, false],
+        [true, true, true, true, false],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [false, true, true, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [true, true, true, true, true],
+        [false, false, false, false, true],
+        [false, false, false, true, false],
+        [false, false, true, false, false],
+        [false, true, false, false, false],
+        [false, true, false, false, false],
+        [false, true, false, false, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, true, true, true, false],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [false, true, true, true, false],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [false, true, true, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, true, true, true, false],
+        [true, false, false, false, true],
+        [true, false, false, false, true],
+        [false, true, true, true, true],
+        [false, false, false, false, true],
+        [false, false, false, true, false],
+        [false, true, true, false, false],
+        [false, false, false, false, false],
+    ],
+    
+    // Punctuation
+    [
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [true, true, true, true, true],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, true, true, false, false],
+        [false, true, true, false, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, true, true, false, false],
+        [false, false, true, false, false],
+        [false, true, false, false, false],
+    ],
+    [
+        [false, false, false, false, false],
+        [false, true, true, false, false],
+        [false, true, true, false, false],
+        [false, false, false, false, false],
+        [false, true, true, false, false],
+        [false, true, true, false, false],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, true, false],
+        [false, false, true, false, false],
+        [false, true, false, false, false],
+        [false, true, false, false, false],
+        [false, true, false, false, false],
+        [false, false, true, false, false],
+        [false, false, false, true, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, true, false, false, false],
+        [false, false, true, false, false],
+        [false, false, false, true, false],
+        [false, false, false, true, false],
+        [false, false, false, true, false],
+        [false, false, true, false, false],
+        [false, true, false, false, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, false, true],
+        [false, false, false, true, false],
+        [false, false, false, true, false],
+        [false, false, true, false, false],
+        [false, true, false, false, false],
+        [false, true, false, false, false],
+        [true, false, false, false, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, false, false],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [true, true, true, true, true],
+        [false, false, true, false, false],
+        [false, false, true, false, false],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [true, true, true, true, true],
+        [false, false, false, false, false],
+        [true, true, true, true, true],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+        [false, false, false, false, false],
+    ],
+    [
+        [false, true, true, true, false],
+        [true, false, false, false, true],
+        [false, false, false, false, true],
+        [false, false, true, true, false],
+        [false, false, true, false, false],
+        [false, false, false, false, false],
+        [false, false, true, false, false],
+        [false, false, false, false, false],
+    ],
+];
@@ -0,0 +1,402 @@
+//! Boundary geometry in world (virtual desktop) coordinates
+//!
+//! Positions and sizes are stored as `i32`, but anything combining a window position
+//! with a monitor size or another offset is computed in `i64` and narrowed with
+//! `saturate`, so extreme virtual desktops clamp instead of overflowing.
+
+use serde::Serialize;
+
+/// Narrows a widened world coordinate back to `i32`, clamping at the ends of its range
+pub const fn saturate(value: i64) -> i32 {
+    if value > i32::MAX as i64 {
+        i32::MAX
+    } else if value < i32::MIN as i64 {
+        i32::MIN
+    } else {
+        value as i32
+    }
+}
+
+/// Parses a `WIDTHxHEIGHT` size such as `1920x1080`
+pub fn parse_size(s: &str) -> Option<(u32, u32)> {
+    let (width, height) = s.split_once(['x', 'X'])?;
+    Some((width.trim().parse().ok()?, height.trim().parse().ok()?))
+}
+
+/// An axis-aligned rectangle; `x`/`y` is the top-left corner
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    pub const fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    /// Exclusive right edge
+    pub const fn right(&self) -> i32 {
+        saturate(self.x as i64 + self.width as i64)
+    }
+
+    /// Exclusive bottom edge
+    pub const fn bottom(&self) -> i32 {
+        saturate(self.y as i64 + self.height as i64)
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.width <= 0 || self.height <= 0
+    }
+
+    pub const fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+    }
+
+    /// The overlapping part of both rectangles, if any
+    pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let width = saturate(self.right().min(other.right()) as i64 - x as i64);
+        let height = saturate(self.bottom().min(other.bottom()) as i64 - y as i64);
+        let rect = Rect::new(x, y, width, height);
+        (!rect.is_empty()).then_some(rect)
+    }
+
+    /// Moves the rectangle by `(dx, dy)`
+    pub const fn offset(&self, dx: i32, dy: i32) -> Rect {
+        Rect::new(saturate(self.x as i64 + dx as i64), saturate(self.y as i64 + dy as i64), self.width, self.height)
+    }
+
+    /// The same rectangle in the coordinates of a window whose top-left is at `window_pos`
+    pub const fn relative_to(&self, window_pos: (i32, i32)) -> Rect {
+        let (x, y) = world_to_window((self.x, self.y), window_pos);
+        Rect::new(x, y, self.width, self.height)
+    }
+}
+
+/// Which monitor corner reported coordinates are measured from.
+///
+/// Only affects coordinates shown to the user; buffers and world math stay top-left.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub enum Origin {
+    /// Screen convention: y grows downwards from the top edge
+    #[default]
+    TopLeft,
+    /// Math convention: y grows upwards from the bottom edge
+    BottomLeft,
+}
+
+impl Origin {
+    /// Converts a world row into the y coordinate shown to the user
+    pub const fn display_y(self, world_y: i32, monitor_height: i32) -> i32 {
+        match self {
+            Origin::TopLeft => world_y,
+            Origin::BottomLeft => saturate(monitor_height as i64 - 1 - world_y as i64),
+        }
+    }
+
+    /// Converts a user-facing y coordinate back into a world row
+    pub const fn world_y(self, display_y: i32, monitor_height: i32) -> i32 {
+        // The flip is its own inverse
+        self.display_y(display_y, monitor_height)
+    }
+
+    /// The reported position of `window`: its corner nearest the origin
+    pub const fn window_position(self, window: Rect, monitor_height: i32) -> (i32, i32) {
+        match self {
+            Origin::TopLeft => (window.x, window.y),
+            Origin::BottomLeft => (window.x, self.display_y(window.bottom() - 1, monitor_height)),
+        }
+    }
+
+    /// The name `parse` accepts
+    pub const fn name(self) -> &'static str {
+        match self {
+            Origin::TopLeft => "top-left",
+            Origin::BottomLeft => "bottom-left",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "top-left" => Some(Origin::TopLeft),
+            "bottom-left" => Some(Origin::BottomLeft),
+            _ => None,
+        }
+    }
+}
+
+/// One of the four monitor edges
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Edge {
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl Edge {
+    pub const ALL: [Edge; 4] = [Edge::Left, Edge::Right, Edge::Top, Edge::Bottom];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Edge::Left => "left",
+            Edge::Right => "right",
+            Edge::Top => "top",
+            Edge::Bottom => "bottom",
+        }
+    }
+}
+
+/// Signed distance from the side of `window` facing `edge` to that monitor edge;
+/// negative once the window extends past it
+pub const fn edge_distance(edge: Edge, window: Rect, monitor_size: (u32, u32)) -> i32 {
+    match edge {
+        Edge::Left => window.x,
+        Edge::Right => saturate(monitor_size.0 as i64 - window.right() as i64),
+        Edge::Top => window.y,
+        Edge::Bottom => saturate(monitor_size.1 as i64 - window.bottom() as i64),
+    }
+}
+
+/// The full band along `edge` across the monitor, in world coordinates
+pub fn monitor_band(edge: Edge, monitor_size: (u32, u32), boundary_size: i32) -> Rect {
+    let monitor_width = saturate(monitor_size.0 as i64);
+    let monitor_height = saturate(monitor_size.1 as i64);
+    let right = saturate(monitor_size.0 as i64 - boundary_size as i64);
+    let bottom = saturate(monitor_size.1 as i64 - boundary_size as i64);
+    match edge {
+        Edge::Left => Rect::new(0, 0, boundary_size, monitor_height),
+        Edge::Right => Rect::new(right, 0, boundary_size, monitor_height),
+        Edge::Top => Rect::new(0, 0, monitor_width, boundary_size),
+        Edge::Bottom => Rect::new(0, bottom, monitor_width, boundary_size),
+    }
+}
+
+/// The part of `window` (world coordinates) that falls inside the band along `edge`.
+///
+/// Bands are half-planes, matching the raster path: everything left of
+/// `boundary_size` is in the left band, even beyond the monitor.
+pub fn band_in_window(edge: Edge, window: Rect, monitor_size: (u32, u32), boundary_size: i32) -> Option<Rect> {
+    let span = |from: i32, to: i32| saturate(to as i64 - from as i64);
+    let band = match edge {
+        Edge::Left => Rect::new(window.x, window.y, span(window.x, boundary_size), window.height),
+        Edge::Right => {
+            let start = window.x.max(saturate(monitor_size.0 as i64 - boundary_size as i64));
+            Rect::new(start, window.y, span(start, window.right()), window.height)
+        }
+        Edge::Top => Rect::new(window.x, window.y, window.width, span(window.y, boundary_size)),
+        Edge::Bottom => {
+            let start = window.y.max(saturate(monitor_size.1 as i64 - boundary_size as i64));
+            Rect::new(window.x, start, window.width, span(start, window.bottom()))
+        }
+    };
+    band.intersect(&window)
+}
+
+/// Whether any band overlaps `window` (world coordinates), i.e. whether the rendered
+/// frame shows any boundary pixels. Windows partly or wholly off the monitor always do.
+pub fn is_touching_boundary(window: Rect, monitor_size: (u32, u32), boundary_size: i32) -> bool {
+    Edge::ALL
+        .into_iter()
+        .any(|edge| band_in_window(edge, window, monitor_size, boundary_size).is_some())
+}
+
+/// The edges whose bands contain the world point `(x, y)`; two in a corner, where
+/// the bands overlap
+pub fn bands_at(x: i32, y: i32, monitor_size: (u32, u32), boundary_size: i32) -> Vec<Edge> {
+    let pixel = Rect::new(x, y, 1, 1);
+    Edge::ALL
+        .into_iter()
+        .filter(|&edge| band_in_window(edge, pixel, monitor_size, boundary_size).is_some())
+        .collect()
+}
+
+/// The visible bands (world coordinates, clipped to `window`) under the world point
+/// `cursor`, e.g. to highlight the band being hovered; both in a corner
+pub fn hovered_bands(cursor: (i32, i32), window: Rect, monitor_size: (u32, u32), boundary_size: i32) -> Vec<(Edge, Rect)> {
+    if !window.contains(cursor.0, cursor.1) {
+        return Vec::new();
+    }
+    bands_at(cursor.0, cursor.1, monitor_size, boundary_size)
+        .into_iter()
+        .filter_map(|edge| band_in_window(edge, window, monitor_size, boundary_size).map(|rect| (edge, rect)))
+        .collect()
+}
+
+/// Where to move `window` so any edge within `threshold` pixels of the matching monitor
+/// edge sits flush against it, or `None` if no edge is near (or all near edges already
+/// are flush). Left and top win when the window is nearly as big as the monitor.
+pub fn magnetic_snap(window: Rect, monitor_size: (u32, u32), threshold: i32) -> Option<(i32, i32)> {
+    let threshold = threshold as i64;
+    let axis = |start: i32, length: i32, monitor: u32| {
+        let (start, length, monitor) = (start as i64, length as i64, monitor as i64);
+        let end = start + length - monitor;
+        let snapped = if start != 0 && start.abs() <= threshold {
+            0
+        } else if end != 0 && end.abs() <= threshold {
+            monitor - length
+        } else {
+            start
+        };
+        saturate(snapped)
+    };
+    let x = axis(window.x, window.width, monitor_size.0);
+    let y = axis(window.y, window.height, monitor_size.1);
+    ((x, y) != (window.x, window.y)).then_some((x, y))
+}
+
+/// The window position as the platform last reported it. Some platforms can't say where
+/// a new window is until it has been placed, so until the first report there is no
+/// position rather than a guessed `(0, 0)`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TrackedPosition(Option<(i32, i32)>);
+
+impl TrackedPosition {
+    /// Takes the result of asking the platform; `None` (it couldn't say) keeps the last
+    /// known position
+    pub fn report(&mut self, position: Option<(i32, i32)>) {
+        if position.is_some() {
+            self.0 = position;
+        }
+    }
+
+    pub const fn get(self) -> Option<(i32, i32)> {
+        self.0
+    }
+
+    pub const fn is_known(self) -> bool {
+        self.0.is_some()
+    }
+
+    /// The last known position, or the origin before the first report
+    pub fn or_default(self) -> (i32, i32) {
+        self.0.unwrap_or_default()
+    }
+}
+
+/// Converts a world point into the coordinates of a window whose top-left is at
+/// `window_pos`, so a fixed world point moves opposite to the window
+pub const fn world_to_window(point: (i32, i32), window_pos: (i32, i32)) -> (i32, i32) {
+    (saturate(point.0 as i64 - window_pos.0 as i64), saturate(point.1 as i64 - window_pos.1 as i64))
+}
+
+/// Unit vector from `window_pos` towards the world origin (the top-left of the monitor),
+/// with y pointing down; `None` when the window is already there
+pub fn direction_to_origin(window_pos: (i32, i32)) -> Option<(f32, f32)> {
+    let (dx, dy) = (-(window_pos.0 as f32), -(window_pos.1 as f32));
+    let length = dx.hypot(dy);
+    (length > 0.0).then(|| (dx / length, dy / length))
+}
+
+/// Every band that overlaps `window`, clipped to it, in world coordinates
+pub fn visible_bands(window: Rect, monitor_size: (u32, u32), boundary_size: i32) -> Vec<(Edge, Rect)> {
+    Edge::ALL
+        .into_iter()
+        .filter_map(|edge| band_in_window(edge, window, monitor_size, boundary_size).map(|rect| (edge, rect)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bottom_left_origin_reports_flipped_y() {
+        // A window resting on the bottom edge of a 1080-row monitor
+        let window = Rect::new(100, 780, 400, 300);
+        assert_eq!(Origin::TopLeft.window_position(window, 1080), (100, 780));
+        assert_eq!(Origin::BottomLeft.window_position(window, 1080), (100, 0));
+        assert_eq!(Origin::BottomLeft.display_y(0, 1080), 1079);
+        assert_eq!(Origin::BottomLeft.world_y(1079, 1080), 0);
+    }
+
+    #[test]
+    fn touching_boundary_for_centred_edge_and_off_screen_windows() {
+        let monitor = (1920, 1080);
+        assert!(!is_touching_boundary(Rect::new(760, 390, 400, 300), monitor, 100));
+        // Its right side just reaches the right band
+        assert!(is_touching_boundary(Rect::new(1421, 390, 400, 300), monitor, 100));
+        assert!(!is_touching_boundary(Rect::new(1420, 390, 400, 300), monitor, 100));
+        assert!(is_touching_boundary(Rect::new(-5000, -5000, 400, 300), monitor, 100));
+        assert!(is_touching_boundary(Rect::new(3000, 390, 400, 300), monitor, 0));
+    }
+
+    #[test]
+    fn direction_to_origin_points_back_at_the_top_left() {
+        assert_eq!(direction_to_origin((100, 0)), Some((-1.0, 0.0)));
+        assert_eq!(direction_to_origin((0, -50)), Some((0.0, 1.0)));
+        assert_eq!(direction_to_origin((300, 400)), Some((-0.6, -0.8)));
+        assert_eq!(direction_to_origin((0, 0)), None);
+    }
+
+    #[test]
+    fn unknown_start_position_is_corrected_by_the_first_report() {
+        let mut position = TrackedPosition::default();
+        // `outer_position` failed while the window was being placed
+        position.report(None);
+        assert!(!position.is_known());
+        assert_eq!(position.or_default(), (0, 0));
+        // The first `Moved` corrects it, and later failures keep it
+        position.report(Some((300, 200)));
+        position.report(None);
+        assert_eq!(position.get(), Some((300, 200)));
+    }
+
+    #[test]
+    fn overridden_monitor_size_moves_the_bands() {
+        let monitor = parse_size("800x600").unwrap();
+        assert_eq!(monitor, (800, 600));
+        assert_eq!(parse_size("800"), None);
+        // Near the right edge of an 800-wide monitor, but mid-screen on a 1920-wide one
+        let window = Rect::new(650, 200, 100, 100);
+        let edges = |monitor_size| visible_bands(window, monitor_size, 100).into_iter().map(|(edge, _)| edge).collect::<Vec<_>>();
+        assert_eq!(edges(monitor), [Edge::Right]);
+        assert!(edges((1920, 1080)).is_empty());
+    }
+
+    #[test]
+    fn hover_finds_the_band_under_the_cursor() {
+        // A 200x200 window hanging 50px over the top-left corner of the monitor
+        let window = Rect::new(-50, -50, 200, 200);
+        let monitor = (1920, 1080);
+        let edges = |cursor| hovered_bands(cursor, window, monitor, 100).into_iter().map(|(edge, _)| edge).collect::<Vec<_>>();
+        // In the corner both bands are hovered, each clipped to the window
+        assert_eq!(
+            hovered_bands((10, 10), window, monitor, 100),
+            [(Edge::Left, Rect::new(-50, -50, 150, 200)), (Edge::Top, Rect::new(-50, -50, 200, 150))]
+        );
+        assert_eq!(edges((99, 120)), [Edge::Left]);
+        assert_eq!(edges((120, 99)), [Edge::Top]);
+        // Inside the window but outside every band, and outside the window altogether
+        assert!(edges((120, 120)).is_empty());
+        assert!(edges((400, 10)).is_empty());
+    }
+
+    #[test]
+    fn magnetic_snap_pulls_in_edges_just_inside_the_threshold() {
+        let monitor = (1920, 1080);
+        let snap = |x, y| magnetic_snap(Rect::new(x, y, 400, 300), monitor, 10);
+        // 10px from the left edge snaps flush, 11px doesn't
+        assert_eq!(snap(10, 500), Some((0, 500)));
+        assert_eq!(snap(11, 500), None);
+        // Past the edge by up to the threshold snaps back too
+        assert_eq!(snap(-10, 500), Some((0, 500)));
+        // Right and bottom edges: 1920 - 400 - 10 = 1510 leaves a 10px gap
+        assert_eq!(snap(1510, 500), Some((1520, 500)));
+        assert_eq!(snap(1509, 500), None);
+        assert_eq!(snap(1510, 775), Some((1520, 780)));
+        // Already flush is left alone
+        assert_eq!(snap(0, 0), None);
+        // Extreme sizes and positions saturate instead of overflowing
+        // 1px short of the right edge of a monitor wider than i32: flush would be x = 2^31,
+        // which saturates to where the window already is
+        assert_eq!(magnetic_snap(Rect::new(i32::MAX, 0, i32::MAX, 10), (u32::MAX, 1080), 5), None);
+        assert_eq!(magnetic_snap(Rect::new(i32::MAX - 5, 0, 100, 100), (u32::MAX, u32::MAX), i32::MAX), Some((0, 0)));
+        assert_eq!(magnetic_snap(Rect::new(i32::MIN, 0, 10, 10), (u32::MAX, 1080), 5), None);
+    }
+}
@@ -0,0 +1,32 @@
+//! Rendering and font logic for the boundary window.
+//!
+//! The windowed binary lives behind the default `windowing` feature; everything in this
+//! library builds without it, so frames can be rendered into plain `u32` buffers.
+
+pub mod atlas;
+pub mod bench;
+pub mod blockcode;
+pub mod color;
+pub mod command;
+pub mod config;
+pub mod contact;
+#[cfg(target_os = "linux")]
+pub mod fbdev;
+pub mod font;
+pub mod geometry;
+pub mod magnifier;
+pub mod monitor;
+pub mod motion;
+pub mod paletted;
+pub mod pattern;
+pub mod profile;
+pub mod psf;
+pub mod rawvideo;
+pub mod recording;
+pub mod render;
+pub mod schedule;
+pub mod snapshot;
+pub mod status;
+pub mod svg;
+pub mod text;
+pub mod verify;
@@ -0,0 +1,87 @@
+//! A zoomed-in inset of the pixels at the monitor edge nearest the window
+
+use crate::geometry::{Edge, Rect, edge_distance};
+
+/// Side length of the inset in window pixels
+pub const INSET_SIZE: i32 = 128;
+/// Nearest-neighbour zoom factor of the inset
+pub const ZOOM: i32 = 8;
+
+const BORDER_COLOR: u32 = 0xFFFFFFFF;
+
+/// The monitor edge closest to `window` (world coordinates) and the signed distance to
+/// it; negative when the window already extends past that edge
+pub fn nearest_edge(window: Rect, monitor_size: (u32, u32)) -> (Edge, i32) {
+    Edge::ALL
+        .into_iter()
+        .map(|edge| (edge, edge_distance(edge, window, monitor_size)))
+        .min_by_key(|&(_, distance)| distance)
+        .unwrap()
+}
+
+/// The `size` x `size` region (window coordinates) to magnify: centred on the nearest
+/// edge line at the middle of the window, then pushed inside the window since only
+/// rendered pixels can be sampled
+pub fn magnifier_source(window: Rect, monitor_size: (u32, u32), size: i32) -> Rect {
+    let (edge, _) = nearest_edge(window, monitor_size);
+    // In i64 so far-off windows on huge monitors can't overflow; the clamp brings the
+    // result back into the window
+    let center_x = window.x as i64 + window.width as i64 / 2;
+    let center_y = window.y as i64 + window.height as i64 / 2;
+    let (world_x, world_y) = match edge {
+        Edge::Left => (0, center_y),
+        Edge::Right => (monitor_size.0 as i64, center_y),
+        Edge::Top => (center_x, 0),
+        Edge::Bottom => (center_x, monitor_size.1 as i64),
+    };
+    let size_x = size.min(window.width);
+    let size_y = size.min(window.height);
+    let x = (world_x - window.x as i64 - size_x as i64 / 2).clamp(0, (window.width - size_x) as i64) as i32;
+    let y = (world_y - window.y as i64 - size_y as i64 / 2).clamp(0, (window.height - size_y) as i64) as i32;
+    Rect::new(x, y, size_x, size_y)
+}
+
+/// Samples `source` from the already rendered frame and blits it, scaled by `ZOOM`, into
+/// the bottom-right corner with a thin border
+pub fn draw_magnifier(buffer: &mut [u32], width: u32, height: u32, source: Rect) {
+    // Copy first: the inset may overlap the region it magnifies
+    let mut samples = Vec::with_capacity((source.width * source.height) as usize);
+    for y in source.y..source.bottom() {
+        let row = (y as u32 * width) as usize;
+        samples.extend_from_slice(&buffer[row + source.x as usize..row + source.right() as usize]);
+    }
+
+    let inset_width = (source.width * ZOOM).min(width as i32);
+    let inset_height = (source.height * ZOOM).min(height as i32);
+    let left = width as i32 - inset_width;
+    let top = height as i32 - inset_height;
+    for y in 0..inset_height {
+        for x in 0..inset_width {
+            let border = x == 0 || y == 0 || x == inset_width - 1 || y == inset_height - 1;
+            let color = if border {
+                BORDER_COLOR
+            } else {
+                samples[((y / ZOOM) * source.width + x / ZOOM) as usize]
+            };
+            buffer[((top + y) as u32 * width + (left + x) as u32) as usize] = color;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_is_centred_on_the_nearest_edge_and_kept_in_the_window() {
+        // 50 px from the left edge: the edge line is left of the window, so the source
+        // is pushed against the window's left side, centred on its middle row
+        let window = Rect::new(50, 400, 400, 300);
+        assert_eq!(nearest_edge(window, (1920, 1080)), (Edge::Left, 50));
+        assert_eq!(magnifier_source(window, (1920, 1080), 16), Rect::new(0, 142, 16, 16));
+
+        // Straddling the top edge: the edge line at world y = 0 is window row 20
+        let window = Rect::new(600, -20, 400, 300);
+        assert_eq!(magnifier_source(window, (1920, 1080), 16), Rect::new(192, 12, 16, 16));
+    }
+}
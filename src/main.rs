@@ -1,45 +1,1081 @@
+use std::collections::VecDeque;
+use std::fmt;
 use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use softbuffer::{Context, Surface};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use softbuffer::{Context, SoftBufferError, Surface};
 use winit::{
     dpi::PhysicalPosition,
-    event::WindowEvent,
-    event_loop::EventLoop,
-    window::Window,
+    event::{DeviceEvent, ElementState, Ime, MouseButton, WindowEvent},
+    event_loop::{ControlFlow, EventLoop, EventLoopProxy},
+    keyboard::{Key, NamedKey},
+    monitor::MonitorHandle,
+    window::{CursorGrabMode, Theme, Window},
+};
+
+use window_messing::atlas::export_font_atlas;
+use window_messing::color::parse_color;
+use window_messing::command::{Command, parse_command};
+use window_messing::config::Config;
+use window_messing::contact::export_contact_sheet;
+use window_messing::font::{BAKED_TEXTS, Font};
+use window_messing::geometry::{
+    Edge, Origin, Rect, TrackedPosition, bands_at, edge_distance, hovered_bands,
+    is_touching_boundary, magnetic_snap, monitor_band, parse_size, saturate, visible_bands,
+};
+use window_messing::monitor::{
+    find_monitor_by_name, format_dpi, monitor_dpi, resolve_mirrored_monitor,
+};
+use window_messing::motion::{Direction, HeldKeys, VelocityTracker, motion_thickness};
+use window_messing::paletted::PalettedBuffer;
+use window_messing::pattern::{HAZARD_YELLOW, Pattern, hazard_stripes, load_ppm};
+use window_messing::profile::FrameStats;
+use window_messing::psf::load_psf;
+use window_messing::rawvideo::RawVideoWriter;
+use window_messing::recording::{RecordedPosition, Recorder, Replay, parse_positions};
+use window_messing::render::{
+    BOUNDARY_ENV_VAR, BoundaryMode, EDGE_LINE_COLOR, IncrementalRenderer, LETTERBOX_COLOR, Label,
+    MAX_RENDER_SCALE, NamedRegion, PRESETS, RenderOptions, Rotation, TextFit, clamp_boundary_size,
+    draw_cursor_glyph, draw_font_grid, draw_monitor_edges, draw_progress, draw_region, draw_trail,
+    draw_window_border, effective_text_scale, fill_circle, fill_debug_index, fit_render_scale,
+    hidden_text_fit, mirror_x, next_preset, parse_aspect, parse_boundary_size, parse_frames,
+    render_frame, rotate_180, rotate_frame, scheme_for_theme, shade_rect,
+};
+use window_messing::schedule::{AnimationClock, Wake, anim_elapsed, next_wake};
+use window_messing::snapshot::{Fallback, export_fallback};
+use window_messing::status::{StatusCell, draw_status_bar};
+use window_messing::svg::render_svg;
+use window_messing::text::{
+    Align, TextStyle, draw_str, draw_underline, glyph_offsets, measure_str, two_columns,
 };
+use window_messing::verify::verify_flag;
+
+/// Set while stdout carries `--raw-out` video, so nothing else may write to it
+static STDOUT_RESERVED: AtomicBool = AtomicBool::new(false);
+
+/// Set by `--quiet`: only errors are printed
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Logs a `DEBUG:` line to stdout, or to stderr while stdout carries video; nothing with `--quiet`
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if QUIET.load(Ordering::Relaxed) {
+            // Errors only
+        } else if STDOUT_RESERVED.load(Ordering::Relaxed) {
+            eprintln!("DEBUG: {}", format_args!($($arg)*));
+        } else {
+            println!("DEBUG: {}", format_args!($($arg)*));
+        }
+    };
+}
+
+/// Logs a `WARN:` line to stderr, except with `--quiet`
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        if !QUIET.load(Ordering::Relaxed) {
+            eprintln!("WARN: {}", format_args!($($arg)*));
+        }
+    };
+}
+
+/// How often `--fbdev` repaints, taking the screen back from anything else drawing there
+#[cfg(target_os = "linux")]
+const FBDEV_REFRESH: Duration = Duration::from_secs(1);
+
+/// Shows the view full screen on the framebuffer device at `path` until killed, as if
+/// the window covered the whole monitor
+#[cfg(target_os = "linux")]
+fn run_fbdev(path: &Path, app: &App) {
+    let mut framebuffer = match window_messing::fbdev::Framebuffer::open(path) {
+        Ok(framebuffer) => framebuffer,
+        Err(err) => {
+            eprintln!("Failed to open framebuffer: {err}");
+            std::process::exit(1);
+        }
+    };
+    let (width, height) = framebuffer.size();
+    let monitor_size = app.monitor_override.map_or((width, height), |size| (size.width, size.height));
+    debug!("Drawing to {} at {width}x{height} ({:?})", path.display(), framebuffer.format());
+    let mut buffer = vec![0; (width * height) as usize];
+    loop {
+        render_frame(&mut buffer, width, height, (0, 0), monitor_size, &app.render_options);
+        framebuffer.present(&buffer, width);
+        std::thread::sleep(FBDEV_REFRESH);
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn run_fbdev(_path: &Path, _app: &App) {
+    eprintln!("--fbdev is only supported on Linux");
+    std::process::exit(1);
+}
+
+/// Prints `text` to stdout, or to stderr while stdout carries video
+fn print_output(text: &str) {
+    if STDOUT_RESERVED.load(Ordering::Relaxed) {
+        eprint!("{text}");
+    } else {
+        print!("{text}");
+    }
+}
 
 fn main() {
-    let event_loop = EventLoop::new().unwrap();
-    event_loop.run_app(&mut App::new()).unwrap();
+    // Checked up front so warnings about the other flags are silenced too
+    QUIET.store(std::env::args().any(|arg| arg == "--quiet"), Ordering::Relaxed);
+    let args = Args::parse();
+    STDOUT_RESERVED.store(args.raw_out.is_some(), Ordering::Relaxed);
+    if args.verify_flag {
+        let monitor_size = args.monitor.unwrap_or(VERIFY_MONITOR_SIZE);
+        match verify_flag(monitor_size) {
+            Ok(flag) => println!("Flag verified: {flag}"),
+            Err(err) => {
+                eprintln!("Flag verification failed: {err}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if let Some(path) = &args.font_atlas {
+        match export_font_atlas(Path::new(path), FONT_ATLAS_SCALE) {
+            Ok(()) => debug!("Wrote font atlas to {path}"),
+            Err(err) => {
+                eprintln!("Failed to write font atlas to {path}: {err}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if let Some(path) = &args.contact_sheet {
+        let monitor_size = args.monitor.unwrap_or(VERIFY_MONITOR_SIZE);
+        match export_contact_sheet(Path::new(path), monitor_size) {
+            Ok(()) => debug!("Wrote contact sheet for a {}x{} monitor to {path}", monitor_size.0, monitor_size.1),
+            Err(err) => {
+                eprintln!("Failed to write contact sheet to {path}: {err}");
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if let Some(path) = args.fbdev.clone() {
+        run_fbdev(&path, &App::new(args));
+        return;
+    }
+    let event_loop = EventLoop::<Command>::with_user_event().build().unwrap();
+    if args.commands {
+        spawn_command_reader(event_loop.create_proxy());
+    }
+    let mut app = App::new(args);
+    event_loop.run_app(&mut app).unwrap();
+    if app.exit_code != 0 {
+        std::process::exit(app.exit_code);
+    }
+}
+
+/// Forwards commands read line by line from stdin to the event loop, until stdin closes
+/// or the loop has exited
+fn spawn_command_reader(proxy: EventLoopProxy<Command>) {
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            match parse_command(&line) {
+                Some(command) => {
+                    if proxy.send_event(command).is_err() {
+                        break;
+                    }
+                }
+                None if line.trim().is_empty() => {}
+                None => warn!("Ignoring unknown command {line:?}"),
+            }
+        }
+    });
+}
+
+/// Monitor size `--verify-flag` and `--contact-sheet` render for unless `--monitor` is given
+const VERIFY_MONITOR_SIZE: (u32, u32) = (1920, 1080);
+
+/// Font pixels per atlas pixel for `--font-atlas`; 1 keeps the atlas pixel-exact
+const FONT_ATLAS_SCALE: u32 = 1;
+
+/// Command line options
+struct Args {
+    /// Render every glyph in the font instead of the boundary view
+    show_font: bool,
+    /// Exit shortly after the first frame has been presented
+    once: bool,
+    /// Convention for reported coordinates
+    origin: Origin,
+    /// Extra text drawn in the window, from `--label X,Y,TEXT` and `--pin-label X,Y,TEXT`
+    labels: Vec<Label>,
+    /// Annotated rectangles, from `--region` and `--pin-region X,Y,W,H,LABEL[,RRGGBB]`
+    regions: Vec<NamedRegion>,
+    /// Render straight into the softbuffer buffer instead of an owned back buffer, from
+    /// `--direct`
+    direct: bool,
+    /// Present through an 8-bit palette-indexed buffer
+    paletted: bool,
+    /// Number of recent window positions to draw as a trail (0 disables it)
+    trail_len: usize,
+    /// Monitor size used for boundary math instead of the real one
+    monitor: Option<(u32, u32)>,
+    /// Write the render state as JSON after the first frame (`-` for stdout)
+    dump_state: Option<String>,
+    /// Collect frame times and print percentiles on exit
+    profile: bool,
+    boundary_mode: BoundaryMode,
+    /// Keep full brightness when the window loses focus
+    no_dim: bool,
+    /// Lay text out by glyph width with kerning instead of fixed cells
+    proportional: bool,
+    /// Render at 1/N resolution and upscale
+    render_scale: u32,
+    /// Render coarser, or not at all, rather than draw more pixels than this per frame
+    max_buffer_pixels: Option<u64>,
+    /// Write every presented frame to stdout as raw RGBA of this size
+    raw_out: Option<(u32, u32)>,
+    /// Draw text black or white per pixel, whichever contrasts with what's underneath
+    auto_contrast: bool,
+    /// Reveal the hidden text one glyph per interval
+    typewriter: Option<Duration>,
+    /// Fill for the bands, from `--band-pattern stripes|PATH.ppm`
+    band_pattern: Option<Pattern>,
+    /// Repaint only the bands that moved instead of every pixel
+    incremental: bool,
+    /// Open on the first monitor whose name contains this, ignoring case
+    monitor_name: Option<String>,
+    /// Start with the compass pointing to the world origin shown
+    compass: bool,
+    /// Name each band the window overlaps with text inside it
+    band_labels: bool,
+    /// Give the hidden text and labels a drop shadow
+    shadow: bool,
+    /// Start with the monitor edge lines shown
+    show_edges: bool,
+    /// Start with the status bar shown
+    status_bar: bool,
+    /// Draw at device pixels, ignoring the display scale factor
+    raw_pixels: bool,
+    /// Dither the gradient boundary mode
+    dither: bool,
+    /// Align the hidden text's blocks to the window's pixel grid
+    snap_text: bool,
+    /// Soften diagonal glyph strokes with partly transparent corner pixels
+    aa_diagonals: bool,
+    /// Draw a distance-to-edge heatmap instead of bands
+    heatmap: bool,
+    /// Thicken the bands while the window is being dragged, by its speed
+    motion_thickness: bool,
+    /// Show the hidden text as a QR-code-like grid of blocks
+    block_code: bool,
+    /// Snap window edges flush to monitor edges within this many pixels, from
+    /// `--magnetic PX`
+    magnetic_threshold: Option<i32>,
+    /// Letterbox the content to this aspect ratio, from `--aspect W:H`
+    target_aspect: Option<f32>,
+    /// Color of the letterbox bars, from `--letterbox-color RRGGBB`
+    letterbox_color: u32,
+    /// Where to render a frame if no drawing surface can be created
+    fallback_png: Option<PathBuf>,
+    /// Draw full screen to this Linux framebuffer device instead of opening a window
+    fbdev: Option<PathBuf>,
+    /// Only print errors and leave every overlay off, for clean recordings
+    quiet: bool,
+    /// Settings loaded from `--config PATH`, before the other flags override them
+    config: Config,
+    /// Where `w` saves the live settings, from `--config PATH`
+    config_path: Option<String>,
+    /// Pick light or dark colors from the OS theme and switch when it changes
+    follow_system_theme: bool,
+    /// Replace the frame with colors hashed from each pixel's buffer index modulo this,
+    /// from `--debug-index N`
+    debug_index: Option<u32>,
+    /// Turn everything clockwise before presenting, for displays mounted sideways or
+    /// upside down, from `--rotate DEGREES` (`--flip` is 180°)
+    rotation: Rotation,
+    /// Mirror everything left to right before presenting, for teleprompters
+    mirror_x: bool,
+    /// Hide the system cursor and draw one into the frame instead
+    software_cursor: bool,
+    /// Accept text input, including IME composition, and show it instead of handling
+    /// shortcut keys
+    ime: bool,
+    /// Accept `window_messing::command` lines on stdin
+    commands: bool,
+    /// Physical monitor size in millimetres, which winit doesn't report, for the DPI
+    /// readout
+    monitor_mm: Option<(u32, u32)>,
+    /// Positions to move the window through, from `--replay FILE`
+    replay: Option<Vec<RecordedPosition>>,
+    /// Append every window move to this file, in the `--replay` format
+    record: Option<String>,
+    /// Write the font as a PNG atlas plus JSON layout to this path and exit
+    font_atlas: Option<String>,
+    /// Render the view from a grid of window positions into one PNG at this path and exit
+    contact_sheet: Option<String>,
+    /// Radius of the rounded inner band corners (0 = square)
+    corner_radius: u32,
+    /// Font for labels and readouts, from `--psf PATH`
+    font: Option<Font>,
+    /// Concentric frames drawn instead of the single band
+    frames: Vec<(i32, u32)>,
+    /// Outline a capture safe area inset by this percentage, from `--safe-area PCT`
+    safe_area_percent: Option<f32>,
+    /// Frame the window interior in this color, from `--window-border RRGGBB`
+    window_border: Option<u32>,
+    /// Render the hidden text headlessly, check it reads back as the flag and exit 0 or 1
+    /// without opening a window; see `window_messing::verify`
+    verify_flag: bool,
+}
+
+impl Args {
+    fn parse() -> Self {
+        Self::parse_from(std::env::args().skip(1).collect())
+    }
+
+    /// Parses `argv`, the command line without the program name
+    fn parse_from(argv: Vec<String>) -> Self {
+        let mut args = Self {
+            show_font: false,
+            once: false,
+            origin: Origin::TopLeft,
+            labels: Vec::new(),
+            regions: Vec::new(),
+            direct: false,
+            paletted: false,
+            trail_len: 0,
+            monitor: None,
+            dump_state: None,
+            profile: false,
+            boundary_mode: BoundaryMode::Flat,
+            no_dim: false,
+            proportional: false,
+            render_scale: 1,
+            max_buffer_pixels: None,
+            raw_out: None,
+            auto_contrast: false,
+            typewriter: None,
+            band_pattern: None,
+            incremental: false,
+            monitor_name: None,
+            compass: false,
+            follow_system_theme: false,
+            band_labels: false,
+            shadow: false,
+            show_edges: false,
+            status_bar: false,
+            raw_pixels: false,
+            dither: false,
+            snap_text: false,
+            aa_diagonals: false,
+            heatmap: false,
+            motion_thickness: false,
+            block_code: false,
+            magnetic_threshold: None,
+            target_aspect: None,
+            letterbox_color: LETTERBOX_COLOR,
+            fallback_png: None,
+            fbdev: None,
+            quiet: false,
+            config: Config::default(),
+            config_path: None,
+            ime: false,
+            commands: false,
+            rotation: Rotation::None,
+            debug_index: None,
+            mirror_x: false,
+            software_cursor: false,
+            monitor_mm: None,
+            replay: None,
+            record: None,
+            font_atlas: None,
+            contact_sheet: None,
+            corner_radius: 0,
+            font: None,
+            frames: Vec::new(),
+            safe_area_percent: None,
+            window_border: None,
+            verify_flag: false,
+        };
+        // A config file is the starting point that every other flag overrides, wherever
+        // it appears on the command line
+        args.config_path = argv.iter().skip_while(|arg| *arg != "--config").nth(1).cloned();
+        if let Some(path) = &args.config_path {
+            match Config::load(Path::new(path)) {
+                Ok(config) => {
+                    args.origin = config.origin;
+                    args.boundary_mode = config.boundary_mode;
+                    args.corner_radius = config.corner_radius;
+                    args.compass = config.show_compass;
+                    args.band_labels = config.band_labels;
+                    args.config = config;
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                    debug!("{path} doesn't exist yet; w will create it");
+                }
+                Err(err) => warn!("Ignoring config {path}: {err}"),
+            }
+        }
+        let mut iter = argv.into_iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--show-font" => args.show_font = true,
+                "--once" => args.once = true,
+                "--direct" => args.direct = true,
+                // The default; kept so existing command lines still work
+                "--back-buffer" => args.direct = false,
+                "--paletted" => args.paletted = true,
+                "--profile" => args.profile = true,
+                "--no-dim" => args.no_dim = true,
+                "--proportional" => args.proportional = true,
+                "--auto-contrast" => args.auto_contrast = true,
+                "--incremental" => args.incremental = true,
+                "--verify-flag" => args.verify_flag = true,
+                "--compass" => args.compass = true,
+                "--follow-theme" => args.follow_system_theme = true,
+                "--band-labels" => args.band_labels = true,
+                "--shadow" => args.shadow = true,
+                "--show-edges" => args.show_edges = true,
+                "--status-bar" => args.status_bar = true,
+                "--raw-pixels" => args.raw_pixels = true,
+                "--dither" => args.dither = true,
+                "--snap-text" => args.snap_text = true,
+                "--aa-diagonals" => args.aa_diagonals = true,
+                "--heatmap" => args.heatmap = true,
+                "--motion-thickness" => args.motion_thickness = true,
+                "--block-code" => args.block_code = true,
+                "--quiet" => args.quiet = true,
+                "--config" => {
+                    // Already loaded above
+                    if iter.next().is_none() {
+                        warn!("--config expects a path");
+                    }
+                }
+                "--ime" => args.ime = true,
+                "--commands" => args.commands = true,
+                "--debug-index" => match iter.next().and_then(|value| value.parse::<u32>().ok()) {
+                    Some(modulus) if modulus > 0 => args.debug_index = Some(modulus),
+                    _ => warn!("--debug-index expects a positive modulus"),
+                },
+                "--flip" => args.rotation = Rotation::Cw180,
+                "--rotate" => match iter.next().as_deref().and_then(Rotation::parse) {
+                    Some(rotation) => args.rotation = rotation,
+                    None => warn!("--rotate expects 0, 90, 180 or 270"),
+                },
+                "--mirror-x" => args.mirror_x = true,
+                "--software-cursor" => args.software_cursor = true,
+                "--origin" => match iter.next().as_deref().and_then(Origin::parse) {
+                    Some(origin) => args.origin = origin,
+                    None => warn!("--origin expects top-left or bottom-left"),
+                },
+                "--trail" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(len) => args.trail_len = len,
+                    None => warn!("--trail expects a number of positions"),
+                },
+                "--monitor" => match iter.next().as_deref().and_then(parse_size) {
+                    Some(size) => args.monitor = Some(size),
+                    None => warn!("--monitor expects WIDTHxHEIGHT"),
+                },
+                "--raw-out" => match iter.next().as_deref().and_then(parse_size) {
+                    Some(size) => args.raw_out = Some(size),
+                    None => warn!("--raw-out expects WIDTHxHEIGHT"),
+                },
+                "--monitor-mm" => match iter.next().as_deref().and_then(parse_size) {
+                    Some(size) => args.monitor_mm = Some(size),
+                    None => warn!("--monitor-mm expects WIDTHxHEIGHT in millimetres"),
+                },
+                "--replay" => match iter.next() {
+                    Some(path) => match std::fs::read_to_string(&path).map_err(|err| err.to_string()).and_then(|text| parse_positions(&text)) {
+                        Ok(positions) => args.replay = Some(positions),
+                        Err(err) => warn!("Failed to load replay {path}: {err}"),
+                    },
+                    None => warn!("--replay expects a path"),
+                },
+                "--corner-radius" => match iter.next().and_then(|n| n.parse().ok()) {
+                    Some(radius) => args.corner_radius = radius,
+                    None => warn!("--corner-radius expects a non-negative integer"),
+                },
+                "--frames" => match iter.next().as_deref().and_then(parse_frames) {
+                    Some(frames) => args.frames = frames,
+                    None => warn!("--frames expects THRESHOLD:RRGGBB pairs separated by commas"),
+                },
+                "--safe-area" => match iter.next().and_then(|value| value.parse::<f32>().ok()) {
+                    Some(percent) if (0.0..=50.0).contains(&percent) => args.safe_area_percent = Some(percent),
+                    _ => warn!("--safe-area expects a percentage from 0 to 50"),
+                },
+                "--aspect" => match iter.next().as_deref().and_then(parse_aspect) {
+                    Some(aspect) => args.target_aspect = Some(aspect),
+                    None => warn!("--aspect expects a ratio such as 16:9 or 1.78"),
+                },
+                "--letterbox-color" => match iter.next().as_deref().and_then(parse_color) {
+                    Some(color) => args.letterbox_color = color,
+                    None => warn!("--letterbox-color expects an RRGGBB color"),
+                },
+                "--magnetic" => match iter.next().and_then(|value| value.parse::<i32>().ok()) {
+                    Some(threshold) if threshold > 0 => args.magnetic_threshold = Some(threshold),
+                    _ => warn!("--magnetic expects a positive number of pixels"),
+                },
+                "--window-border" => match iter.next().as_deref().and_then(parse_color) {
+                    Some(color) => args.window_border = Some(color),
+                    None => warn!("--window-border expects an RRGGBB color"),
+                },
+                "--psf" => match iter.next() {
+                    Some(path) => match load_psf(&path) {
+                        Ok(font) => args.font = Some(font),
+                        Err(err) => warn!("Failed to load font {path}: {err}"),
+                    },
+                    None => warn!("--psf expects a path to a PSF font"),
+                },
+                "--record" => match iter.next() {
+                    Some(path) => args.record = Some(path),
+                    None => warn!("--record expects a path"),
+                },
+                "--font-atlas" => match iter.next() {
+                    Some(path) => args.font_atlas = Some(path),
+                    None => warn!("--font-atlas expects a path"),
+                },
+                "--contact-sheet" => match iter.next() {
+                    Some(path) => args.contact_sheet = Some(path),
+                    None => warn!("--contact-sheet expects a path"),
+                },
+                "--fbdev" => match iter.next() {
+                    Some(path) => args.fbdev = Some(PathBuf::from(path)),
+                    None => warn!("--fbdev expects a device path such as /dev/fb0"),
+                },
+                "--fallback-png" => match iter.next() {
+                    Some(path) => args.fallback_png = Some(PathBuf::from(path)),
+                    None => warn!("--fallback-png expects a path"),
+                },
+                "--dump-state" => match iter.next() {
+                    Some(path) => args.dump_state = Some(path),
+                    None => warn!("--dump-state expects a path or -"),
+                },
+                "--boundary-mode" => match iter.next().as_deref().and_then(BoundaryMode::parse) {
+                    Some(mode) => args.boundary_mode = mode,
+                    None => warn!("--boundary-mode expects flat, proximity, gradient or edge-only"),
+                },
+                "--render-scale" => match iter.next().and_then(|n| n.parse().ok()).filter(|&n| n >= 1) {
+                    Some(scale) => args.render_scale = scale,
+                    None => warn!("--render-scale expects a positive integer"),
+                },
+                "--max-buffer-pixels" => match iter.next().and_then(|n| n.parse().ok()).filter(|&n| n >= 1) {
+                    Some(pixels) => args.max_buffer_pixels = Some(pixels),
+                    None => warn!("--max-buffer-pixels expects a positive integer"),
+                },
+                "--typewriter" => match iter.next().and_then(|ms| ms.parse().ok()).filter(|&ms| ms > 0) {
+                    Some(ms) => args.typewriter = Some(Duration::from_millis(ms)),
+                    None => warn!("--typewriter expects a positive number of milliseconds per glyph"),
+                },
+                "--monitor-name" => match iter.next() {
+                    Some(name) => args.monitor_name = Some(name),
+                    None => warn!("--monitor-name expects part of a monitor name"),
+                },
+                "--band-pattern" => match iter.next().as_deref() {
+                    Some("stripes") => args.band_pattern = Some(hazard_stripes(HAZARD_YELLOW, 16)),
+                    Some(path) => match load_ppm(path) {
+                        Ok(pattern) => args.band_pattern = Some(pattern),
+                        Err(err) => warn!("Failed to load band pattern {path}: {err}"),
+                    },
+                    None => warn!("--band-pattern expects stripes or a path to a PPM image"),
+                },
+                "--label" => match iter.next().as_deref().and_then(parse_label) {
+                    Some(label) => args.labels.push(label),
+                    None => warn!("--label expects X,Y,TEXT"),
+                },
+                "--pin-label" => match iter.next().as_deref().and_then(parse_label) {
+                    Some(label) => args.labels.push(Label { pinned: true, ..label }),
+                    None => warn!("--pin-label expects X,Y,TEXT in monitor coordinates"),
+                },
+                "--region" => match iter.next().as_deref().and_then(parse_region) {
+                    Some(region) => args.regions.push(region),
+                    None => warn!("--region expects X,Y,W,H,LABEL with an optional ,RRGGBB color"),
+                },
+                "--pin-region" => match iter.next().as_deref().and_then(parse_region) {
+                    Some(region) => args.regions.push(NamedRegion { pinned: true, ..region }),
+                    None => warn!("--pin-region expects X,Y,W,H,LABEL in monitor coordinates, with an optional ,RRGGBB color"),
+                },
+                other => warn!("Ignoring unknown argument {other}"),
+            }
+        }
+        for label in &mut args.labels {
+            label.style.proportional |= args.proportional;
+            label.style.auto_contrast |= args.auto_contrast;
+            label.style.shadow = label.style.shadow.or(args.shadow.then_some(TEXT_SHADOW));
+        }
+        if args.quiet {
+            args.hide_overlays();
+        }
+        args
+    }
+
+    /// Turns off everything drawn over the bands and the hidden text, whichever flags
+    /// or config asked for it. `--label` text stays, since it's content rather than
+    /// diagnostics.
+    fn hide_overlays(&mut self) {
+        self.compass = false;
+        self.band_labels = false;
+        self.show_edges = false;
+        self.status_bar = false;
+        self.safe_area_percent = None;
+        self.trail_len = 0;
+        self.config.show_magnifier = false;
+    }
+}
+
+/// Parses `X,Y,W,H,LABEL` with an optional trailing `,RRGGBB`; regions are white
+/// otherwise. Labels may contain commas as long as they don't end in a color.
+fn parse_region(s: &str) -> Option<NamedRegion> {
+    let mut parts = s.splitn(5, ',');
+    let mut number = || parts.next()?.trim().parse::<i32>().ok();
+    let (x, y, width, height) = (number()?, number()?, number()?, number()?);
+    let rest = parts.next()?;
+    let (label, color) = match rest.rsplit_once(',').and_then(|(label, color)| Some((label, parse_color(color)?))) {
+        Some((label, color)) => (label, color),
+        None => (rest, REGION_COLOR),
+    };
+    (width > 0 && height > 0).then(|| NamedRegion { rect: Rect::new(x, y, width, height), label: label.to_string(), color, pinned: false })
+}
+
+fn parse_label(s: &str) -> Option<Label> {
+    let mut parts = s.splitn(3, ',');
+    let x = parts.next()?.trim().parse().ok()?;
+    let y = parts.next()?.trim().parse().ok()?;
+    let text = parts.next()?.to_string();
+    Some(Label { text, x, y, style: TextStyle { scale: 2, ..TextStyle::default() }, pinned: false })
+}
+
+/// Where a frame is drawn before it's presented.
+///
+/// `present` is the only point softbuffer promises the frame is shown, but the contents of
+/// `buffer_mut` aren't guaranteed to be private until then on every backend. Intermittent
+/// flicker drawing straight into it was reported, but hasn't been reproduced on any
+/// platform here, so `BackBuffer` is the default: it renders into memory we own and
+/// copies the finished frame over in a single pass, so the softbuffer buffer only ever
+/// holds complete frames, at the cost of one extra copy. `Direct` skips that copy and is
+/// opt-in with `--direct`.
+/// `Paletted` also goes through the back buffer, then packs the frame into a
+/// `PalettedBuffer` and expands that into the softbuffer buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Buffering {
+    Direct,
+    BackBuffer,
+    Paletted,
+}
+
+/// Reads the boundary size from the environment, falling back to `default` when the
+/// variable is unset or unparseable
+fn boundary_size_from_env(default: i32) -> i32 {
+    match std::env::var(BOUNDARY_ENV_VAR) {
+        Ok(value) => parse_boundary_size(&value).unwrap_or_else(|| {
+            warn!("Ignoring {BOUNDARY_ENV_VAR}={value:?}, expected an integer");
+            default
+        }),
+        Err(_) => default,
+    }
+}
+
+/// Snapshot of everything that determines the boundary render, for bug reports
+#[derive(Serialize)]
+struct RenderState {
+    window_position: (i32, i32),
+    inner_size: (u32, u32),
+    monitor_size: (u32, u32),
+    /// Index of the window's monitor in `available_monitors`, if it could be found
+    monitor_index: Option<usize>,
+    boundary_size: i32,
+    text_scale: i32,
+    origin: Origin,
+    /// Full band along each monitor edge, world coordinates
+    bands: Vec<(Edge, Rect)>,
+    /// Parts of the bands that overlap the window, world coordinates
+    visible_bands: Vec<(Edge, Rect)>,
+    /// Whether any band overlaps the window
+    touching_boundary: bool,
+    /// Whether the window's monitor is mirrored by another one
+    mirrored: bool,
+}
+
+/// Why the app couldn't do something it needs a live window for
+#[derive(Debug)]
+enum AppError {
+    /// There's no window yet, or it has been closed
+    NoWindow,
+    /// softbuffer couldn't create or size a context or surface
+    Surface(SoftBufferError),
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoWindow => write!(f, "no window to draw to"),
+            Self::Surface(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl From<SoftBufferError> for AppError {
+    fn from(err: SoftBufferError) -> Self {
+        Self::Surface(err)
+    }
 }
 
 struct App {
     window: Option<Arc<Window>>,
     context: Option<Context<Arc<Window>>>,
     surface: Option<Surface<Arc<Window>, Arc<Window>>>,
-    window_position: PhysicalPosition<i32>,
+    /// Where the platform last said the window is
+    position: TrackedPosition,
     monitor_size: winit::dpi::PhysicalSize<u32>,
+    decorations: bool,
+    show_font: bool,
+    once: bool,
+    /// Set once the `--once` frame is on screen; the app exits when it passes
+    exit_at: Option<Instant>,
+    render_options: RenderOptions,
+    buffering: Buffering,
+    back_buffer: Vec<u32>,
+    /// The last frame as palette indices with `--paletted`
+    paletted: PalettedBuffer,
+    /// Frames go to stdout as raw video with `--raw-out`
+    raw_out: Option<RawVideoWriter>,
+    /// Pixel budget per frame from `--max-buffer-pixels`
+    max_buffer_pixels: Option<u64>,
+    /// The `--render-scale` asked for; `render_options` holds the one actually used
+    requested_render_scale: u32,
+    /// Render scale `fit_render_scale` last chose, to log only when it changes
+    fitted_render_scale: Option<Option<u32>>,
+    /// Most recent window positions, oldest first
+    trail: VecDeque<PhysicalPosition<i32>>,
+    /// Window speed for `--motion-thickness`
+    velocity: Option<VelocityTracker>,
+    /// Arrow keys moving the window
+    held_keys: HeldKeys,
+    /// Snap distance for magnetic monitor edges
+    magnetic_threshold: Option<i32>,
+    /// Where the last magnetic snap moved the window; its own `Moved` mustn't snap again
+    magnet_target: Option<PhysicalPosition<i32>>,
+    trail_len: usize,
+    /// Virtual monitor size from `--monitor`; the window itself is still sized from the
+    /// real monitor
+    monitor_override: Option<winit::dpi::PhysicalSize<u32>>,
+    dump_state: Option<String>,
+    /// Frame times, only allocated with `--profile`
+    frame_stats: Option<FrameStats>,
+    focused: bool,
+    dim_when_unfocused: bool,
+    /// Cursor position relative to the window, `None` while it's outside
+    cursor: Option<PhysicalPosition<f64>>,
+    /// Keep `monitor_size` at its last value instead of following the current monitor
+    lock_monitor: bool,
+    /// Whether the current monitor overlaps another one, i.e. displays are mirrored
+    mirrored: bool,
+    /// Time per revealed glyph of the hidden text, from `--typewriter`
+    reveal_interval: Option<Duration>,
+    /// When the reveal began, in animation time; set on the first frame
+    reveal_start: Option<Instant>,
+    /// Time source for the reveal and replay, slowed down by the `x` key
+    clock: AnimationClock,
+    /// Band layer kept between frames with `--incremental`
+    incremental: Option<IncrementalRenderer>,
+    monitor_name: Option<String>,
+    /// Index into `PRESETS` of the current color scheme
+    preset: usize,
+    scale_factor: f64,
+    /// Swap colors with the OS light/dark theme, from `--follow-theme`
+    follow_system_theme: bool,
+    monitor_mm: Option<(u32, u32)>,
+    /// Show the monitor's DPI in the bottom-left corner
+    show_dpi: bool,
+    /// Keep the text scale fixed instead of following the display scale factor
+    raw_pixels: bool,
+    /// Skip the cursor readout and hover tint, from `--quiet`
+    quiet: bool,
+    /// Headless output used instead when the surface can't be created
+    fallback_png: Option<PathBuf>,
+    /// Process exit status once the event loop ends
+    exit_code: i32,
+    /// Result of the last `check_text_fit`
+    text_fit: Option<TextFit>,
+    /// Draw lines along the outermost monitor pixels
+    show_edges: bool,
+    /// Show position, edge distances and frame time in a bar along the bottom
+    show_status: bool,
+    /// How long the previous frame took to draw, for the status bar
+    last_frame_time: Option<Duration>,
+    /// Text typed so far in `--ime` mode
+    ime: Option<ImeText>,
+    /// Text set by `Command::SetText`, shown under the top-left indicators
+    command_text: Option<String>,
+    /// Present every frame turned clockwise, from `--rotate` or `--flip`
+    rotation: Rotation,
+    /// Draw the buffer index pattern instead of the scene, for checking row strides
+    debug_index: Option<u32>,
+    /// Present every frame mirrored left to right, from `--mirror-x`
+    mirror_x: bool,
+    /// Draw the cursor into the frame, with the system one hidden
+    software_cursor: bool,
+    /// Color of the 1px frame drawn around the window interior, on top of everything
+    window_border: Option<u32>,
+    /// Annotated rectangles drawn over everything while `show_regions` is set
+    regions: Vec<NamedRegion>,
+    show_regions: bool,
+    /// Where `w` saves the live settings
+    config_path: PathBuf,
+    /// Show the key bindings panel
+    show_help: bool,
+    /// Recorded moves being played back; starts with the first frame
+    replay: Option<Replay>,
+    /// Open `--record` file; dropped after a write error
+    recorder: Option<Recorder<std::fs::File>>,
+    /// Trackpad demo marker in window coordinates, moved by relative mouse motion while
+    /// the cursor is grabbed; `None` when the demo is off
+    trackpad: Option<(f64, f64)>,
+}
+
+/// Brightness of boundaries and text while the window is unfocused
+const UNFOCUSED_BRIGHTNESS: f32 = 0.6;
+
+/// Gap in pixels between the cursor and its coordinate readout
+const CURSOR_READOUT_OFFSET: i32 = 12;
+
+/// Text entered in `--ime` mode
+#[derive(Debug, Default)]
+struct ImeText {
+    /// Text already committed
+    committed: String,
+    /// In-progress composition shown after `committed`
+    preedit: String,
+    /// Byte range of the active segment of `preedit`, if the IME reported one
+    preedit_cursor: Option<(usize, usize)>,
+    /// Whether an IME is active, in which case text only arrives through it
+    enabled: bool,
+}
+
+/// Where `--ime` text starts, from the left edge and the vertical middle of the window
+const IME_TEXT_MARGIN: i32 = 8;
+
+/// Pixels added to or removed from the window width and height by `+` and `-`
+const RESIZE_STEP: i32 = 32;
+/// Smallest size the window can be resized to in each direction, with `-` or by hand
+const MIN_WINDOW_SIZE: u32 = 64;
+
+/// The digit keys that pick one of `BAKED_TEXTS`, following its length. Only 1-9 have
+/// keys, which the indexing checks at compile time.
+const BAKED_TEXT_KEYS: &str = ["1", "1-2", "1-3", "1-4", "1-5", "1-6", "1-7", "1-8", "1-9"][BAKED_TEXTS.len() - 1];
+
+/// Every key binding handled by `handle_key`, as `(key, description)`, for the help
+/// overlay
+const KEY_BINDINGS: &[(&str, &str)] = &[
+    ("? F1", "show or hide this help"),
+    (BAKED_TEXT_KEYS, "pick the hidden text"),
+    ("d", "toggle window decorations"),
+    ("m", "toggle maximized"),
+    ("+ -", "grow or shrink the window"),
+    ("arrows", "move the window"),
+    ("l", "lock the monitor size"),
+    ("s", "next color scheme"),
+    ("c", "toggle the compass"),
+    ("e", "toggle the monitor edge lines"),
+    ("r", "toggle the --region outlines"),
+    ("b", "toggle the status bar"),
+    ("x", "cycle slow motion (1x, 2x, 4x)"),
+    ("z", "toggle the magnifier"),
+    ("i", "toggle the DPI readout"),
+    ("t", "toggle the trackpad demo"),
+    ("v", "export an SVG of the layout"),
+    ("j", "print the render state as JSON"),
+    ("p", "print frame time stats"),
+    ("w", "save settings to the config file"),
+];
+
+/// The window movement an arrow key stands for
+fn arrow_direction(key: Key<&str>) -> Option<Direction> {
+    match key {
+        Key::Named(NamedKey::ArrowLeft) => Some(Direction::Left),
+        Key::Named(NamedKey::ArrowRight) => Some(Direction::Right),
+        Key::Named(NamedKey::ArrowUp) => Some(Direction::Up),
+        Key::Named(NamedKey::ArrowDown) => Some(Direction::Down),
+        _ => None,
+    }
+}
+
+/// The help overlay text: one line per key binding, keys in a fixed-width column
+fn help_text() -> String {
+    two_columns(KEY_BINDINGS)
 }
 
+/// Default outline and label color of a `--region`
+const REGION_COLOR: u32 = 0xFFFFFFFF;
+
+/// Tint blended over the band under the cursor
+const HOVER_TINT: u32 = 0xFFFFFFFF;
+const HOVER_TINT_ALPHA: u8 = 64;
+
+/// Margin around the help panel and padding inside it, in pixels
+const HELP_PANEL_MARGIN: i32 = 16;
+const HELP_PANEL_PADDING: i32 = 8;
+
+/// Draws `help_text` over a translucent dark panel in the top-left of the window
+fn draw_help(buffer: &mut [u32], width: u32, height: u32, options: &RenderOptions) {
+    let text = help_text();
+    let style = TextStyle { scale: 2, line_spacing: 4, proportional: false, ..options.text };
+    let (text_width, text_height) = measure_str(&options.font, &text, &style);
+    let panel = Rect::new(
+        HELP_PANEL_MARGIN,
+        HELP_PANEL_MARGIN,
+        text_width + 2 * HELP_PANEL_PADDING,
+        text_height + 2 * HELP_PANEL_PADDING,
+    );
+    shade_rect(buffer, width, height, panel, 0xFF000000, 192);
+    draw_str(buffer, &options.font, &text, panel.x + HELP_PANEL_PADDING, panel.y + HELP_PANEL_PADDING, width, &style);
+}
+
+/// `(dx, dy, color)` of the `--shadow` drop shadow
+const TEXT_SHADOW: (i32, i32, u32) = (2, 2, 0xFF404040); // Dark grey
+
+/// Where `w` saves the settings when no `--config` path was given
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+/// Height of the typewriter reveal progress bar along the bottom of the window
+const REVEAL_PROGRESS_HEIGHT: i32 = 4;
+
+/// Radius and color of the trackpad demo marker
+const TRACKPAD_MARKER_RADIUS: i32 = 8;
+const TRACKPAD_MARKER_COLOR: u32 = 0xFF00C0FF; // Light blue
+
+/// How long a `--once` frame stays on screen before exiting
+const ONCE_LINGER: Duration = Duration::from_millis(500);
+
 impl App {
-    fn new() -> Self {
+    fn new(args: Args) -> Self {
         Self { 
             window: None,
             context: None,
             surface: None,
-            window_position: PhysicalPosition::new(0, 0),
+            position: TrackedPosition::default(),
             monitor_size: winit::dpi::PhysicalSize::new(0, 0),
+            decorations: true,
+            show_font: args.show_font,
+            once: args.once,
+            exit_at: None,
+            buffering: match (args.paletted, args.direct) {
+                (true, _) => Buffering::Paletted,
+                (false, true) => Buffering::Direct,
+                (false, false) => Buffering::BackBuffer,
+            },
+            back_buffer: Vec::new(),
+            paletted: PalettedBuffer::new(0, 0, [0xFF000000; 256]),
+            raw_out: args.raw_out.map(|(width, height)| RawVideoWriter::new(width, height, args.config.background)),
+            max_buffer_pixels: args.max_buffer_pixels,
+            requested_render_scale: args.render_scale,
+            fitted_render_scale: None,
+            trail: VecDeque::with_capacity(args.trail_len),
+            velocity: args.motion_thickness.then(VelocityTracker::new),
+            held_keys: HeldKeys::new(),
+            magnetic_threshold: args.magnetic_threshold,
+            magnet_target: None,
+            trail_len: args.trail_len,
+            dump_state: args.dump_state,
+            frame_stats: args.profile.then(FrameStats::new),
+            focused: true,
+            dim_when_unfocused: !args.no_dim,
+            cursor: None,
+            lock_monitor: false,
+            mirrored: false,
+            reveal_interval: args.typewriter,
+            reveal_start: None,
+            clock: AnimationClock::new(Instant::now()),
+            incremental: args.incremental.then(IncrementalRenderer::new),
+            monitor_name: args.monitor_name,
+            trackpad: None,
+            preset: 0,
+            scale_factor: 1.0,
+            follow_system_theme: args.follow_system_theme,
+            monitor_mm: args.monitor_mm,
+            show_dpi: false,
+            raw_pixels: args.raw_pixels,
+            quiet: args.quiet,
+            fallback_png: args.fallback_png,
+            exit_code: 0,
+            text_fit: None,
+            show_edges: args.show_edges,
+            show_status: args.status_bar,
+            last_frame_time: None,
+            ime: args.ime.then(ImeText::default),
+            command_text: None,
+            rotation: args.rotation,
+            debug_index: args.debug_index,
+            mirror_x: args.mirror_x,
+            software_cursor: args.software_cursor,
+            window_border: args.window_border,
+            regions: args.regions,
+            show_regions: true,
+            show_help: false,
+            config_path: PathBuf::from(args.config_path.as_deref().unwrap_or(DEFAULT_CONFIG_PATH)),
+            replay: args.replay.map(Replay::new),
+            recorder: args.record.and_then(|path| {
+                let recorder = std::fs::File::create(&path).and_then(|file| Recorder::new(file, Instant::now()));
+                recorder.inspect_err(|err| warn!("Failed to start recording to {path}: {err}")).ok()
+            }),
+            monitor_override: args.monitor.map(|(width, height)| winit::dpi::PhysicalSize::new(width, height)),
+            render_options: RenderOptions {
+                origin: args.origin,
+                labels: args.labels,
+                text: TextStyle {
+                    color: args.config.text_color,
+                    proportional: args.proportional,
+                    auto_contrast: args.auto_contrast,
+                    shadow: args.shadow.then_some(TEXT_SHADOW),
+                    snap: args.snap_text,
+                    aa_diagonals: args.aa_diagonals,
+                    ..TextStyle::default()
+                },
+                background: args.config.background,
+                boundary_color: args.config.boundary_color,
+                show_magnifier: args.config.show_magnifier,
+                boundary_mode: args.boundary_mode,
+                render_scale: args.render_scale,
+                band_pattern: args.band_pattern,
+                show_compass: args.compass,
+                band_labels: args.band_labels,
+                corner_radius: args.corner_radius,
+                font: Arc::new(args.font.unwrap_or_else(Font::builtin)),
+                frames: args.frames,
+                safe_area_percent: args.safe_area_percent,
+                dither: args.dither,
+                heatmap: args.heatmap,
+                target_aspect: args.target_aspect,
+                letterbox_color: args.letterbox_color,
+                block_code: args.block_code,
+                boundary_size: boundary_size_from_env(clamp_boundary_size(args.config.boundary_size)),
+                ..RenderOptions::default()
+            },
         }
     }
 }
 
-impl winit::application::ApplicationHandler for App {
+impl winit::application::ApplicationHandler<Command> for App {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         if self.window.is_none() {
             let monitors: Vec<_> = event_loop.available_monitors().collect();
-            let primary_monitor = monitors.first().unwrap();
-            let monitor_size = primary_monitor.size();
-            self.monitor_size = monitor_size;
+            let names: Vec<_> = monitors.iter().map(|monitor| monitor.name()).collect();
+            let chosen = self.monitor_name.as_deref().and_then(|query| {
+                let index = find_monitor_by_name(names.iter().map(Option::as_deref), query);
+                if index.is_none() {
+                    warn!("No monitor name contains {query:?} (found {names:?}); using the primary monitor");
+                }
+                index
+            });
+            let monitor = &monitors[chosen.unwrap_or(0)];
+            let monitor_size = monitor.size();
+            self.monitor_size = self.monitor_override.unwrap_or(monitor_size);
+            if let Some(size) = self.monitor_override {
+                debug!("Using virtual monitor {}x{} for boundaries", size.width, size.height);
+            }
             
             let window_size = winit::dpi::PhysicalSize::new(
                 monitor_size.width / 2,
@@ -49,18 +1085,53 @@ impl winit::application::ApplicationHandler for App {
             let window_attributes = Window::default_attributes()
                 .with_title("Boundary Window")
                 .with_inner_size(window_size)
-                .with_resizable(false);
+                .with_min_inner_size(winit::dpi::PhysicalSize::new(MIN_WINDOW_SIZE, MIN_WINDOW_SIZE))
+                .with_decorations(self.decorations);
+            // Only place the window explicitly when asked to, so the platform's default
+            // placement still applies otherwise
+            let window_attributes = match chosen {
+                Some(_) => {
+                    let origin = monitor.position();
+                    debug!("Opening on monitor {:?} at {origin:?}", monitor.name());
+                    window_attributes.with_position(PhysicalPosition::new(
+                        origin.x + (monitor_size.width / 4) as i32,
+                        origin.y + (monitor_size.height / 4) as i32,
+                    ))
+                }
+                None => window_attributes,
+            };
             
             let window = Arc::new(event_loop.create_window(window_attributes).unwrap());
             
-            self.window_position = window.outer_position().unwrap_or_default();
-            
-            let context = Context::new(window.clone()).unwrap();
-            let surface = Surface::new(&context, window.clone()).unwrap();
+            // Some platforms only know the position once placement is final; until then the
+            // first `Moved` is authoritative and nothing is drawn from a guessed (0, 0)
+            self.position.report(window.outer_position().ok().map(|position| (position.x, position.y)));
+            if !self.position.is_known() {
+                debug!("Window position not available yet; waiting for the first move");
+            }
+            self.apply_scale_factor(window.scale_factor());
+            if self.ime.is_some() {
+                window.set_ime_allowed(true);
+                // Candidate windows open next to where the text is drawn
+                let y = window.inner_size().height as i32 / 2;
+                window.set_ime_cursor_area(PhysicalPosition::new(IME_TEXT_MARGIN, y), winit::dpi::PhysicalSize::new(1, 1));
+            }
+            if let Some(theme) = window.theme() {
+                self.apply_theme(theme);
+            }
+            if self.software_cursor {
+                window.set_cursor_visible(false);
+            }
             
-            self.window = Some(window);
-            self.context = Some(context);
-            self.surface = Some(surface);
+            self.window = Some(window.clone());
+            if let Err(err) = self.rebuild_surface() {
+                eprintln!("Failed to create a drawing surface: {err}");
+                self.render_without_surface(&window);
+                self.shutdown();
+                event_loop.exit();
+                return;
+            }
+            self.check_text_fit();
             
             self.redraw();
         }
@@ -73,756 +1144,1030 @@ impl winit::application::ApplicationHandler for App {
         event: winit::event::WindowEvent,
     ) {
         match event {
-            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::CloseRequested => {
+                self.shutdown();
+                event_loop.exit();
+            }
             WindowEvent::RedrawRequested => {
                 self.redraw();
             }
             WindowEvent::Moved(position) => {
-                self.window_position = position;
+                self.position.report(Some((position.x, position.y)));
+                self.snap_to_edges(position);
+                self.record_trail(position);
+                if let Some(velocity) = &mut self.velocity {
+                    velocity.record((position.x, position.y), Instant::now());
+                }
+                self.record_move(position);
+                self.log_position();
                 self.redraw();
             }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                // The accompanying `Resized` resizes the surface; only the text scale changes here
+                self.apply_scale_factor(scale_factor);
+                self.request_redraw();
+            }
+            WindowEvent::Focused(focused) => {
+                self.focused = focused;
+                if !focused {
+                    // Releases go to the newly focused window, so nothing would stop the motion
+                    self.held_keys = HeldKeys::new();
+                }
+                self.update_brightness();
+                self.request_redraw();
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                self.cursor = Some(position);
+                self.request_redraw();
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.cursor = None;
+                self.request_redraw();
+            }
+            WindowEvent::MouseInput { state: ElementState::Pressed, button: MouseButton::Left, .. } => {
+                self.report_clicked_band();
+            }
+            WindowEvent::ThemeChanged(theme) => {
+                self.apply_theme(theme);
+                self.request_redraw();
+            }
+            WindowEvent::Resized(_) => {
+                self.refresh_geometry();
+                self.check_text_fit();
+                self.request_redraw();
+            }
+            WindowEvent::KeyboardInput { event, .. } if event.state == ElementState::Pressed && self.ime.is_some() => {
+                self.handle_text_key(event.logical_key.as_ref(), event.text.as_deref());
+            }
+            WindowEvent::KeyboardInput { event, .. } if let Some(direction) = arrow_direction(event.logical_key.as_ref()) => {
+                self.held_keys.set(direction, event.state == ElementState::Pressed, Instant::now());
+            }
+            WindowEvent::KeyboardInput { event, .. } if event.state == ElementState::Pressed && !event.repeat => {
+                self.handle_key(event.logical_key.as_ref());
+            }
+            WindowEvent::Ime(event) => self.handle_ime(event),
+            _ => (),
+        }
+    }
+
+    fn user_event(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop, command: Command) {
+        command.apply(&mut self.command_text);
+        self.request_redraw();
+    }
+
+    fn device_event(
+        &mut self,
+        _event_loop: &winit::event_loop::ActiveEventLoop,
+        _device_id: winit::event::DeviceId,
+        event: DeviceEvent,
+    ) {
+        // Raw deltas keep arriving while the cursor itself is confined or locked
+        if let (DeviceEvent::MouseMotion { delta: (dx, dy) }, Some((x, y)), Some(window)) = (event, self.trackpad, &self.window) {
+            let size = window.inner_size();
+            self.trackpad = Some(((x + dx).clamp(0.0, size.width as f64), (y + dy).clamp(0.0, size.height as f64)));
+            self.request_redraw();
+        }
+    }
+
+    fn exiting(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop) {
+        // Exits not initiated here (e.g. the platform ending the loop) still clean up
+        self.shutdown();
+    }
+
+    fn about_to_wait(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
+        if self.exit_at.is_some_and(|exit_at| Instant::now() >= exit_at) {
+            self.shutdown();
+            event_loop.exit();
+            return;
+        }
+        if self.next_reveal_at().is_some_and(|at| Instant::now() >= at) {
+            self.request_redraw();
+        }
+        self.advance_replay();
+        self.step_held_keys();
+        if let Some(velocity) = &mut self.velocity
+            && velocity.settle(Instant::now())
+        {
+            self.request_redraw();
+        }
+        // Every timed feature reports its next deadline here; with none the loop idles
+        let next_replay_at = self.replay.as_ref().and_then(Replay::next_at).map(|at| self.clock.wall_time(at));
+        let settles_at = self.velocity.as_ref().and_then(VelocityTracker::settles_at);
+        let deadlines = [self.exit_at, self.next_reveal_at(), next_replay_at, settles_at, self.held_keys.next_step_at()];
+        event_loop.set_control_flow(match next_wake(Instant::now(), deadlines) {
+            Wake::Idle => ControlFlow::Wait,
+            Wake::At(at) => ControlFlow::WaitUntil(at),
+            Wake::Now => ControlFlow::Poll,
+        });
+    }
+}
+
+impl App {
+    fn handle_key(&mut self, key: Key<&str>) {
+        match key {
+            Key::Character("d") => self.toggle_decorations(),
+            Key::Character("v") => self.export_svg(),
+            Key::Character("m") => self.toggle_maximized(),
+            Key::Character("j") => self.write_state("-"),
+            Key::Character("p") => self.print_frame_stats(),
+            Key::Character("l") => self.toggle_monitor_lock(),
+            Key::Character("s") => self.cycle_color_preset(),
+            Key::Character("t") => self.toggle_trackpad(),
+            Key::Character("w") => self.save_config(),
+            Key::Character("+" | "=") => self.resize_by(RESIZE_STEP),
+            Key::Character("-") => self.resize_by(-RESIZE_STEP),
+            Key::Character("?") | Key::Named(NamedKey::F1) => {
+                self.show_help = !self.show_help;
+                self.request_redraw();
+            }
+            Key::Character("i") => {
+                self.show_dpi = !self.show_dpi;
+                self.request_redraw();
+            }
+            Key::Character(digit @ ("1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9")) => {
+                let index = digit.parse::<usize>().unwrap() - 1;
+                if index < BAKED_TEXTS.len() && index != self.render_options.baked_text {
+                    self.render_options.baked_text = index;
+                    debug!("Showing baked text {}", index + 1);
+                    self.check_text_fit();
+                    self.request_redraw();
+                }
+            }
+            Key::Character("b") => {
+                self.show_status = !self.show_status;
+                self.request_redraw();
+            }
+            Key::Character("e") => {
+                self.show_edges = !self.show_edges;
+                self.request_redraw();
+            }
+            Key::Character("r") => {
+                self.show_regions = !self.show_regions;
+                self.request_redraw();
+            }
+            Key::Character("x") => {
+                self.clock.cycle_time_scale(Instant::now());
+                debug!("Animations at {}x slow motion", self.clock.time_scale());
+                self.request_redraw();
+            }
+            Key::Character("c") => {
+                self.render_options.show_compass = !self.render_options.show_compass;
+                self.request_redraw();
+            }
+            Key::Character("z") => {
+                self.render_options.show_magnifier = !self.render_options.show_magnifier;
+                self.request_redraw();
+            }
             _ => (),
         }
     }
+
+    fn update_brightness(&mut self) {
+        self.render_options.brightness = if self.dim_when_unfocused && !self.focused {
+            UNFOCUSED_BRIGHTNESS
+        } else {
+            1.0
+        };
+    }
+
+    /// Typing in `--ime` mode: backspace deletes, and without an active IME printable keys
+    /// append their text
+    fn handle_text_key(&mut self, key: Key<&str>, text: Option<&str>) {
+        let Some(ime) = &mut self.ime else {
+            return;
+        };
+        match (key, text) {
+            (Key::Named(NamedKey::Backspace), _) => {
+                ime.committed.pop();
+            }
+            (_, Some(text)) if !ime.enabled && !text.chars().any(char::is_control) => ime.committed.push_str(text),
+            _ => return,
+        }
+        self.request_redraw();
+    }
+
+    fn handle_ime(&mut self, event: Ime) {
+        let Some(ime) = &mut self.ime else {
+            return;
+        };
+        match event {
+            Ime::Enabled => ime.enabled = true,
+            Ime::Disabled => {
+                ime.enabled = false;
+                ime.preedit.clear();
+                ime.preedit_cursor = None;
+            }
+            Ime::Preedit(text, cursor) => {
+                ime.preedit = text;
+                ime.preedit_cursor = cursor;
+            }
+            Ime::Commit(text) => {
+                ime.committed.push_str(&text);
+                ime.preedit.clear();
+                ime.preedit_cursor = None;
+            }
+        }
+        self.request_redraw();
+    }
+
+    fn request_redraw(&self) {
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
+
+    fn record_trail(&mut self, position: PhysicalPosition<i32>) {
+        if self.trail_len == 0 {
+            return;
+        }
+        if self.trail.len() == self.trail_len {
+            self.trail.pop_front();
+        }
+        self.trail.push_back(position);
+    }
+
+    /// Grows (or shrinks, for negative `step`) the window by `step` pixels in each
+    /// direction, keeping it between `MIN_WINDOW_SIZE` and the monitor size
+    fn resize_by(&mut self, step: i32) {
+        let Some(window) = &self.window else {
+            return;
+        };
+        let size = window.inner_size();
+        let resize = |current: u32, max: u32| (current as i32 + step).clamp(MIN_WINDOW_SIZE as i32, max.max(MIN_WINDOW_SIZE) as i32) as u32;
+        let requested = winit::dpi::PhysicalSize::new(resize(size.width, self.monitor_size.width), resize(size.height, self.monitor_size.height));
+        if requested == size {
+            return;
+        }
+        debug!("Requested inner size {}x{}", requested.width, requested.height);
+        // `Some` means it was applied right away and no `Resized` event will follow
+        if window.request_inner_size(requested).is_some() {
+            self.refresh_geometry();
+            self.request_redraw();
+        }
+    }
+
+    fn toggle_maximized(&mut self) {
+        if let Some(window) = &self.window {
+            let maximized = !window.is_maximized();
+            window.set_maximized(maximized);
+            // The follow-up `Resized`/`Moved` events refresh the geometry and redraw
+            debug!("Requested maximized = {maximized}");
+        }
+    }
+
+    /// Re-reads the window position and the size of the monitor it's on, e.g. after a
+    /// resize that may also have moved it
+    fn refresh_geometry(&mut self) {
+        if let Some(window) = &self.window {
+            self.position.report(window.outer_position().ok().map(|position| (position.x, position.y)));
+            if self.lock_monitor {
+                return;
+            }
+            let Some(current) = window.current_monitor() else {
+                return;
+            };
+            // Mirrored displays all claim the same spot, and which one is "current" can
+            // change from one call to the next, so always settle on the same one
+            let monitors: Vec<_> = window.available_monitors().collect();
+            let rects: Vec<_> = monitors.iter().map(monitor_rect).collect();
+            let monitor = match monitors.iter().position(|monitor| *monitor == current) {
+                Some(index) => {
+                    let (chosen, mirrored) = resolve_mirrored_monitor(&rects, index);
+                    if mirrored != self.mirrored {
+                        self.mirrored = mirrored;
+                        if mirrored {
+                            debug!("Mirrored monitors detected; using {:?}", monitors[chosen].name());
+                        } else {
+                            debug!("Monitor is no longer mirrored");
+                        }
+                    }
+                    &monitors[chosen]
+                }
+                None => &current,
+            };
+            self.monitor_size = self.monitor_override.unwrap_or(monitor.size());
+        }
+    }
+
+    /// Writes the live settings to the config path for `--config` to load next time
+    fn save_config(&self) {
+        match Config::from_options(&self.render_options).save(&self.config_path) {
+            Ok(()) => debug!("Saved settings to {}", self.config_path.display()),
+            Err(err) => warn!("Failed to save settings to {}: {err}", self.config_path.display()),
+        }
+    }
+
+    /// Switches to the colors for `theme` when following the system theme
+    fn apply_theme(&mut self, theme: Theme) {
+        if !self.follow_system_theme {
+            return;
+        }
+        let scheme = scheme_for_theme(theme == Theme::Light);
+        scheme.apply(&mut self.render_options);
+        debug!("System theme {theme:?}, color scheme {}", scheme.name);
+    }
+
+    fn cycle_color_preset(&mut self) {
+        self.preset = next_preset(self.preset);
+        let scheme = &PRESETS[self.preset];
+        scheme.apply(&mut self.render_options);
+        debug!("Color scheme {}", scheme.name);
+        self.request_redraw();
+    }
+
+    /// Grabs the cursor (confined, or locked where confining isn't supported) and shows a
+    /// marker driven by relative motion, or releases it again
+    fn toggle_trackpad(&mut self) {
+        let Some(window) = &self.window else {
+            return;
+        };
+        if self.trackpad.take().is_some() {
+            if let Err(err) = window.set_cursor_grab(CursorGrabMode::None) {
+                warn!("Failed to release the cursor: {err}");
+            }
+            // `--software-cursor` keeps the system one hidden for good
+            if !self.software_cursor {
+                window.set_cursor_visible(true);
+            }
+            debug!("Trackpad demo off");
+        } else {
+            let grabbed = window
+                .set_cursor_grab(CursorGrabMode::Confined)
+                .map(|()| "confined")
+                .or_else(|_| window.set_cursor_grab(CursorGrabMode::Locked).map(|()| "locked"));
+            match grabbed {
+                Ok(mode) => {
+                    let size = window.inner_size();
+                    self.trackpad = Some((size.width as f64 / 2.0, size.height as f64 / 2.0));
+                    window.set_cursor_visible(false);
+                    debug!("Trackpad demo on, cursor {mode}");
+                }
+                Err(err) => warn!("Cursor grab isn't supported here: {err}"),
+            }
+        }
+        self.request_redraw();
+    }
+
+    fn toggle_monitor_lock(&mut self) {
+        self.lock_monitor = !self.lock_monitor;
+        debug!(
+            "Monitor size {} at {}x{}",
+            if self.lock_monitor { "locked" } else { "unlocked" },
+            self.monitor_size.width,
+            self.monitor_size.height,
+        );
+        if !self.lock_monitor {
+            self.refresh_geometry();
+        }
+        self.request_redraw();
+    }
+
+    fn log_position(&self) {
+        if let Some(window) = &self.window {
+            let size = window.inner_size();
+            let (x, y) = self.position.or_default();
+            let rect = Rect::new(x, y, size.width as i32, size.height as i32);
+            let (x, y) = self.render_options.origin.window_position(rect, self.monitor_size.height as i32);
+            debug!("Window pos: ({x}, {y}) [{:?}]", self.render_options.origin);
+        }
+    }
+
+    fn apply_scale_factor(&mut self, scale_factor: f64) {
+        self.scale_factor = scale_factor;
+        self.render_options.text.scale = effective_text_scale(scale_factor, self.raw_pixels);
+        debug!(
+            "Scale factor {scale_factor} -> text scale {}",
+            self.render_options.text.scale,
+        );
+        self.check_text_fit();
+    }
+
+    /// Writes the current boundary layout to `boundary.svg` in the working directory
+    fn export_svg(&self) {
+        let Some(window) = &self.window else {
+            return;
+        };
+        let size = window.inner_size();
+        let svg = render_svg(
+            self.position.or_default(),
+            (size.width, size.height),
+            (self.monitor_size.width, self.monitor_size.height),
+            &self.render_options,
+        );
+        match std::fs::write("boundary.svg", svg) {
+            Ok(()) => debug!("Wrote boundary.svg"),
+            Err(err) => warn!("Failed to write boundary.svg: {err}"),
+        }
+    }
+
+    fn toggle_decorations(&mut self) {
+        if let Some(window) = &self.window {
+            self.decorations = !self.decorations;
+            window.set_decorations(self.decorations);
+            
+            // Without decorations the outer position is the client origin, so refresh it
+            self.position.report(window.outer_position().ok().map(|position| (position.x, position.y)));
+            debug!(
+                "Decorations {} (inner size now {:?}); some platforms ignore this or only apply it after the next configure",
+                if self.decorations { "on" } else { "off" },
+                window.inner_size(),
+            );
+            
+            window.request_redraw();
+        }
+    }
 }
 
 impl App {
     fn redraw(&mut self) {
+        let frame_start = Instant::now();
+        self.update_reveal();
+        let status_cells = if self.show_status { self.status_cells() } else { Vec::new() };
         if let (Some(window), Some(surface)) = (&self.window, &mut self.surface) {
             let size = window.inner_size();
             let width = size.width;
             let height = size.height;
             
-            surface.resize(
-                NonZeroU32::new(width).unwrap(),
-                NonZeroU32::new(height).unwrap(),
-            ).unwrap();
-            
-            let mut buffer = surface.buffer_mut().unwrap();
-            
-            // Fill with black background
-            for pixel in buffer.iter_mut() {
-                *pixel = 0xFF000000; // Black
+            // Minimized or not yet configured windows report a zero size; there is nothing to draw
+            let (Some(nz_width), Some(nz_height)) = (NonZeroU32::new(width), NonZeroU32::new(height)) else {
+                return;
+            };
+            if let Some(max_pixels) = self.max_buffer_pixels {
+                let fitted = fit_render_scale(width, height, self.requested_render_scale, max_pixels);
+                if self.fitted_render_scale != Some(fitted) {
+                    self.fitted_render_scale = Some(fitted);
+                    match fitted {
+                        Some(scale) if scale == self.requested_render_scale => {
+                            debug!("{width}x{height} frame fits in {max_pixels} pixels at render scale {scale}");
+                        }
+                        Some(scale) => debug!("{width}x{height} frame is over {max_pixels} pixels; rendering at 1/{scale} scale"),
+                        None => warn!(
+                            "{width}x{height} frame is over {max_pixels} pixels even at 1/{MAX_RENDER_SCALE} scale; not drawing"
+                        ),
+                    }
+                }
+                match fitted {
+                    Some(scale) => self.render_options.render_scale = scale,
+                    None => return,
+                }
             }
+            // The frame is drawn upright at the window's size, so everything in it sees the
+            // real geometry; a quarter turn only swaps the size of what gets presented
+            let (surface_width, surface_height) = if self.rotation.is_quarter_turn() { (nz_height, nz_width) } else { (nz_width, nz_height) };
+            surface.resize(surface_width, surface_height).unwrap();
             
-            // Calculate which edges are near the screen boundary (100px threshold)
-            const BOUNDARY_SIZE: i32 = 100;
-            
-            // Check current position vs stored position
-            let current_pos = window.outer_position().unwrap_or_default();
-            if current_pos != self.window_position {
-                self.window_position = current_pos;
-            }
+            // Check current position vs stored position; keep the last known one if the
+            // platform can't say
+            self.position.report(window.outer_position().ok().map(|position| (position.x, position.y)));
             
-            let pos = self.window_position;
-            let monitor_width = self.monitor_size.width as i32;
-            let monitor_height = self.monitor_size.height as i32;
+            let mut buffer = surface.buffer_mut().unwrap();
+            let target: &mut [u32] = match self.buffering {
+                Buffering::Direct if !self.rotation.is_quarter_turn() => &mut buffer,
+                _ => {
+                    self.back_buffer.resize((width * height) as usize, self.render_options.background);
+                    &mut self.back_buffer
+                }
+            };
             
-            // Draw green boundaries where appropriate
-            for y in 0..height {
-                for x in 0..width {
-                    let idx = (y * width + x) as usize;
-                    let mut draw_green = false;
-                    
-                    // Calculate world coordinates for this pixel
-                    let world_x = pos.x + x as i32;
-                    let world_y = pos.y + y as i32;
-                    
-                    // Left boundary (world x < BOUNDARY_SIZE)
-                    if world_x < BOUNDARY_SIZE {
-                        draw_green = true;
-                    }
-                    
-                    // Right boundary (world x >= monitor_width - BOUNDARY_SIZE)
-                    if world_x >= monitor_width - BOUNDARY_SIZE {
-                        draw_green = true;
-                    }
-                    
-                    // Top boundary (world y < BOUNDARY_SIZE)
-                    if world_y < BOUNDARY_SIZE {
-                        draw_green = true;
-                    }
-                    
-                    // Bottom boundary (world y >= monitor_height - BOUNDARY_SIZE)
-                    if world_y >= monitor_height - BOUNDARY_SIZE {
-                        draw_green = true;
+            if self.debug_index.is_some() {
+                // Filled in on the presented buffer below
+            } else if self.show_font {
+                target.fill(self.render_options.background);
+                draw_font_grid(target, width, height);
+            } else if !self.position.is_known() {
+                target.fill(self.render_options.background);
+                draw_str(target, &self.render_options.font, "WAITING FOR WINDOW POSITION", 4, 4, width, &TextStyle { scale: 2, ..self.render_options.text });
+            } else {
+                let (x, y) = self.position.or_default();
+                let pos = PhysicalPosition::new(x, y);
+                let monitor_size = (self.monitor_size.width, self.monitor_size.height);
+                // Only this frame's bands grow; everything else sees the set boundary size
+                let boundary_size = self.render_options.boundary_size;
+                if let Some(velocity) = &self.velocity {
+                    let extra = motion_thickness(velocity.speed(frame_start));
+                    self.render_options.boundary_size = clamp_boundary_size(boundary_size + extra);
+                }
+                match &mut self.incremental {
+                    Some(incremental) => incremental.render(target, width, height, (pos.x, pos.y), monitor_size, &self.render_options),
+                    None => render_frame(target, width, height, (pos.x, pos.y), monitor_size, &self.render_options),
+                }
+                self.render_options.boundary_size = boundary_size;
+                if !self.trail.is_empty() {
+                    draw_trail(target, width, height, (pos.x, pos.y), self.trail.iter().map(|p| (p.x, p.y)));
+                }
+                if let Some(cursor) = self.cursor.filter(|_| !self.quiet) {
+                    let world = (saturate(pos.x as i64 + cursor.x.floor() as i64), saturate(pos.y as i64 + cursor.y.floor() as i64));
+                    let window_rect = Rect::new(pos.x, pos.y, width as i32, height as i32);
+                    for (_, band) in hovered_bands(world, window_rect, monitor_size, self.render_options.boundary_size) {
+                        shade_rect(target, width, height, band.relative_to((pos.x, pos.y)), HOVER_TINT, HOVER_TINT_ALPHA);
                     }
-                    
-                    if draw_green {
-                        buffer[idx] = 0xFF00FF00; // Green
+                }
+                if self.show_edges {
+                    draw_monitor_edges(target, width, height, (pos.x, pos.y), monitor_size, EDGE_LINE_COLOR);
+                }
+                if let Some(shown) = self.render_options.reveal_chars {
+                    let bar = Rect::new(0, height as i32 - REVEAL_PROGRESS_HEIGHT, width as i32, REVEAL_PROGRESS_HEIGHT);
+                    let fraction = shown as f32 / self.render_options.hidden_text().source.len() as f32;
+                    let options = &self.render_options;
+                    draw_progress(target, bar, fraction, options.text.color, options.background, width, height);
+                }
+                if self.lock_monitor {
+                    draw_str(target, &self.render_options.font, "LOCKED", 4, 4, width, &TextStyle { scale: 2, ..self.render_options.text });
+                }
+                if let Some(text) = &self.command_text {
+                    let style = TextStyle { scale: 2, ..self.render_options.text };
+                    let y = 8 + measure_str(&self.render_options.font, "LOCKED", &style).1;
+                    draw_str(target, &self.render_options.font, text, 4, y, width, &style);
+                }
+                if self.show_dpi {
+                    // The real monitor, not a `--monitor` override, since DPI is physical
+                    let dpi = window.current_monitor().and_then(|monitor| {
+                        let size = monitor.size();
+                        monitor_dpi((size.width, size.height), self.monitor_mm)
+                    });
+                    let text = format_dpi(dpi, self.scale_factor);
+                    let style = TextStyle { scale: 2, ..self.render_options.text };
+                    draw_str(target, &self.render_options.font, &text, 4, height as i32 - 4 - measure_str(&self.render_options.font, &text, &style).1, width, &style);
+                }
+                if let Some((x, y)) = self.trackpad {
+                    fill_circle(target, width, height, (x as i32, y as i32), TRACKPAD_MARKER_RADIUS, TRACKPAD_MARKER_COLOR, 255);
+                }
+                if let Some(ime) = &self.ime {
+                    draw_ime_text(target, width, height, ime, &self.render_options);
+                }
+                if self.show_help {
+                    draw_help(target, width, height, &self.render_options);
+                }
+                if self.show_status {
+                    let style = TextStyle { scale: 2, ..self.render_options.text };
+                    draw_status_bar(target, width, height, &self.render_options.font, &status_cells, &style);
+                }
+                if let Some(cursor) = self.cursor.filter(|_| !self.quiet) {
+                    draw_cursor_readout(target, width, height, (pos.x, pos.y), cursor, self.monitor_size.height, &self.render_options);
+                }
+                if let Some(cursor) = self.cursor.filter(|_| self.software_cursor) {
+                    draw_cursor_glyph(target, width, height, cursor.x.floor() as i32, cursor.y.floor() as i32);
+                }
+                if self.show_regions {
+                    for region in &self.regions {
+                        draw_region(target, width, height, region, (pos.x, pos.y), &self.render_options.font);
                     }
                 }
+                if let Some(color) = self.window_border {
+                    draw_window_border(target, width, height, color);
+                }
             }
             
-            
-            // Position text way off screen above the monitor
-            let off_screen_x = monitor_width / 2; // Keep horizontally centered
-            let off_screen_y = -(monitor_height as i32) - 1000; // Well above screen
-            
-            // Convert off-screen coordinates to window coordinates
-            let text_x = off_screen_x - pos.x;
-            let text_y = off_screen_y - pos.y;
-            
-            
-            Self::draw_text(&mut buffer, text_x, text_y, width);
-            
+            // Done on the presented pixels only, so nothing else has to know about it. The
+            // index view shows the presented buffer's own layout, so it skips them all.
+            if let Some(modulus) = self.debug_index {
+                fill_debug_index(&mut buffer, modulus);
+            } else {
+                let size = (width, height);
+                present_back_buffer(&mut self.back_buffer, &mut self.paletted, &mut self.buffering, self.rotation, size, &mut buffer);
+            }
+            let width = surface_width.get();
+            if self.debug_index.is_none() {
+                if self.rotation == Rotation::Cw180 {
+                    rotate_180(&mut buffer);
+                }
+                if self.mirror_x {
+                    mirror_x(&mut buffer, width);
+                }
+            }
+            if let Some(raw_out) = &mut self.raw_out
+                && let Err(err) = raw_out.write_frame(&mut std::io::stdout().lock(), &buffer, width)
+            {
+                // Usually the reader has gone away, so stop rather than fail every frame
+                warn!("Stopping raw video output: {err}");
+                self.raw_out = None;
+            }
             buffer.present().unwrap();
+            self.last_frame_time = Some(frame_start.elapsed());
+            if let Some(stats) = &mut self.frame_stats {
+                stats.record(frame_start.elapsed());
+            }
+            self.frame_presented();
+        }
+    }
+    
+    /// Advances the typewriter reveal; `reveal_chars` goes back to `None` once the whole
+    /// text is showing
+    fn update_reveal(&mut self) {
+        let Some(interval) = self.reveal_interval else {
+            return;
+        };
+        let now = Instant::now();
+        let start = *self.reveal_start.get_or_insert_with(|| self.clock.at(now));
+        let shown = (anim_elapsed(&self.clock, start, now).as_nanos() / interval.as_nanos()) as usize;
+        self.render_options.reveal_chars = (shown < self.render_options.hidden_text().source.len()).then_some(shown);
+    }
+
+    /// When the next glyph of an in-progress reveal is due
+    fn next_reveal_at(&self) -> Option<Instant> {
+        let (interval, start, shown) = (self.reveal_interval?, self.reveal_start?, self.render_options.reveal_chars?);
+        Some(self.clock.wall_time(start + interval * (shown as u32 + 1)))
+    }
+
+    fn record_move(&mut self, position: PhysicalPosition<i32>) {
+        if let Some(recorder) = &mut self.recorder
+            && let Err(err) = recorder.record(Instant::now(), position.x, position.y)
+        {
+            warn!("Stopping recording after a write error: {err}");
+            self.recorder = None;
+        }
+    }
+
+    /// With `--magnetic`, pulls a window that was just moved to `position` flush against
+    /// any monitor edge it's close to. The snap's own `Moved` is let through as is.
+    fn snap_to_edges(&mut self, position: PhysicalPosition<i32>) {
+        if self.magnet_target.take() == Some(position) {
+            return;
+        }
+        let (Some(threshold), Some(window)) = (self.magnetic_threshold, &self.window) else {
+            return;
+        };
+        let size = window.inner_size();
+        let rect = Rect::new(position.x, position.y, size.width as i32, size.height as i32);
+        if let Some((x, y)) = magnetic_snap(rect, (self.monitor_size.width, self.monitor_size.height), threshold) {
+            let target = PhysicalPosition::new(x, y);
+            window.set_outer_position(target);
+            self.magnet_target = Some(target);
+        }
+    }
+
+    /// Moves the window along with any held arrow keys once a step is due; the resulting
+    /// `Moved` renders it
+    fn step_held_keys(&mut self) {
+        let now = Instant::now();
+        let (Some(window), Some(due)) = (&self.window, self.held_keys.next_step_at()) else {
+            return;
+        };
+        if now < due {
+            return;
+        }
+        let (dx, dy) = self.held_keys.step(now);
+        if (dx, dy) != (0, 0) {
+            let (x, y) = self.position.or_default();
+            window.set_outer_position(PhysicalPosition::new(x.saturating_add(dx), y.saturating_add(dy)));
+        }
+    }
+
+    /// Moves the window to the latest due replay position; the resulting `Moved` renders it
+    fn advance_replay(&mut self) {
+        let (Some(replay), Some(window)) = (&mut self.replay, &self.window) else {
+            return;
+        };
+        if let Some(position) = replay.take_due(self.clock.at(Instant::now())) {
+            window.set_outer_position(PhysicalPosition::new(position.x, position.y));
+        }
+        if replay.is_finished() {
+            debug!("Replay finished");
+            self.replay = None;
+        }
+    }
+
+    fn frame_presented(&mut self) {
+        if let Some(replay) = &mut self.replay {
+            replay.start(self.clock.at(Instant::now()));
+        }
+        if self.once && self.exit_at.is_none() {
+            self.exit_at = Some(Instant::now() + ONCE_LINGER);
+        }
+        if let Some(path) = self.dump_state.take() {
+            self.write_state(&path);
         }
     }
     
-    fn draw_text(buffer: &mut [u32], x: i32, y: i32, buffer_width: u32) {
-        const SCALE: i32 = 3;
-        let mut offset_x = 0;
-        for char_data in TEXT_BITMAPS.iter() {
-            Self::draw_char(buffer, x + offset_x, y, char_data, buffer_width, SCALE);
-            offset_x += 6 * SCALE; // 5 pixels wide + 1 pixel spacing, scaled
+    /// Finishes outstanding work before the event loop exits: writes a `--dump-state`
+    /// that no frame has triggered yet, prints the frame stats and flushes the `--record`
+    /// file. Safe to call more than once; each step only runs the first time.
+    fn shutdown(&mut self) {
+        if let Some(path) = self.dump_state.take() {
+            self.write_state(&path);
+        }
+        if let Some(stats) = self.frame_stats.take() {
+            print_output(&stats.report());
+        }
+        if let Some(recorder) = self.recorder.take()
+            && let Err(err) = recorder.finish()
+        {
+            warn!("Failed to finish recording: {err}");
+        }
+    }
+
+    fn print_frame_stats(&self) {
+        if let Some(stats) = &self.frame_stats {
+            print_output(&stats.report());
         }
     }
     
-    fn draw_char(buffer: &mut [u32], x: i32, y: i32, char_data: &[[bool; 5]; 8], buffer_width: u32, scale: i32) {
-        for (row, line) in char_data.iter().enumerate() {
-            for (col, &pixel) in line.iter().enumerate() {
-                if pixel {
-                    // Draw a scale x scale block for each pixel
-                    for dy in 0..scale {
-                        for dx in 0..scale {
-                            let px = x + (col as i32 * scale) + dx;
-                            let py = y + (row as i32 * scale) + dy;
-                            if px >= 0 && py >= 0 && px < buffer_width as i32 {
-                                let idx = (py as u32 * buffer_width + px as u32) as usize;
-                                if idx < buffer.len() {
-                                    buffer[idx] = 0xFFFFFFFF; // White text
-                                }
-                            }
-                        }
-                    }
-                }
+    fn render_state(&self) -> Option<RenderState> {
+        let window = self.window.as_ref()?;
+        let size = window.inner_size();
+        let current = window.current_monitor();
+        let monitor_size = (self.monitor_size.width, self.monitor_size.height);
+        let boundary_size = self.render_options.boundary_size;
+        let (x, y) = self.position.or_default();
+        let window_rect = Rect::new(x, y, size.width as i32, size.height as i32);
+        Some(RenderState {
+            window_position: self.position.or_default(),
+            inner_size: (size.width, size.height),
+            monitor_size,
+            monitor_index: window.available_monitors().position(|monitor| Some(monitor) == current),
+            boundary_size,
+            text_scale: self.render_options.text.scale,
+            origin: self.render_options.origin,
+            bands: Edge::ALL.into_iter().map(|edge| (edge, monitor_band(edge, monitor_size, boundary_size))).collect(),
+            visible_bands: visible_bands(window_rect, monitor_size, boundary_size),
+            touching_boundary: self.is_touching_boundary(),
+            mirrored: self.mirrored,
+        })
+    }
+
+    /// Drops the softbuffer context and surface and creates them afresh for the current
+    /// window, sized to it, then asks for a redraw. On failure both are left unset.
+    fn rebuild_surface(&mut self) -> Result<(), AppError> {
+        let window = self.window.clone().ok_or(AppError::NoWindow)?;
+        // The surface borrows from the context, so it has to go first
+        self.surface = None;
+        self.context = None;
+        let context = Context::new(window.clone())?;
+        let mut surface = Surface::new(&context, window.clone())?;
+        let size = window.inner_size();
+        if let (Some(width), Some(height)) = (NonZeroU32::new(size.width), NonZeroU32::new(size.height)) {
+            surface.resize(width, height)?;
+        }
+        self.context = Some(context);
+        self.surface = Some(surface);
+        window.request_redraw();
+        Ok(())
+    }
+
+    /// Fallback for when softbuffer can't draw to `window`: renders the frame it would
+    /// have shown to `--fallback-png`, or explains how to get one
+    fn render_without_surface(&mut self, window: &Window) {
+        let size = window.inner_size();
+        let window_pos = self.position.or_default();
+        let monitor_size = (self.monitor_size.width, self.monitor_size.height);
+        match export_fallback(self.fallback_png.as_deref(), (size.width, size.height), window_pos, monitor_size, &self.render_options) {
+            Ok(Fallback::Png(path)) => debug!("Rendered the frame to {} instead", path.display()),
+            Ok(Fallback::Unconfigured) => {
+                eprintln!("Pass --fallback-png PATH to render the view to an image instead");
+                self.exit_code = 1;
+            }
+            Err(err) => {
+                eprintln!("Failed to write the --fallback-png image: {err}");
+                self.exit_code = 1;
             }
         }
     }
-}
 
-const fn string_to_bytes<const N:usize>(s: &str) -> Option<[u8; N]> {
-    if s.len() == N {
-        let mut i = 0;
-        let mut out: [u8; N] = [0; N];
-        while i < N {
-            out[i] = s.as_bytes()[i];
-            i += 1;
+    /// Warns when the window (or any window on this monitor) is too small to show the
+    /// whole hidden text at once; logs only when the answer changes
+    fn check_text_fit(&mut self) {
+        let Some(window) = &self.window else {
+            return;
+        };
+        let size = window.inner_size();
+        let len = self.render_options.hidden_text().source.len();
+        let monitor_size = (self.monitor_size.width, self.monitor_size.height);
+        let fit = hidden_text_fit((size.width, size.height), monitor_size, len, self.render_options.text.scale);
+        if self.text_fit == Some(fit) {
+            return;
+        }
+        self.text_fit = Some(fit);
+        match fit {
+            TextFit::Fits => {}
+            TextFit::TooSmall { needed: (width, height) } => {
+                warn!("The hidden text is {width}x{height}; the window must be at least that big to show it whole");
+            }
+            TextFit::Never { needed: (width, height) } => {
+                warn!("The hidden text is {width}x{height}, bigger than the monitor; it can never be seen whole");
+            }
         }
-        Some(out)
-    } else {
-        None
     }
-}
 
-const LEN: usize = 28;
-const TEXT_SOURCE: [u8;LEN] = string_to_bytes("ictf{Teeheehee_you_found_me}").unwrap();
+    /// Status bar contents: position, distance to each edge and the last frame time
+    fn status_cells(&self) -> Vec<StatusCell> {
+        let Some(window) = &self.window else {
+            return Vec::new();
+        };
+        let size = window.inner_size();
+        let (window_x, window_y) = self.position.or_default();
+        let window = Rect::new(window_x, window_y, size.width as i32, size.height as i32);
+        let monitor_size = (self.monitor_size.width, self.monitor_size.height);
+        let (x, y) = self.render_options.origin.window_position(window, self.monitor_size.height as i32);
+        let distances = Edge::ALL
+            .map(|edge| format!("{}{}", &edge.name()[..1].to_uppercase(), edge_distance(edge, window, monitor_size)))
+            .join(" ");
+        let frame = match self.last_frame_time {
+            Some(time) => format!("{:.1}ms", time.as_secs_f64() * 1000.0),
+            None => "-".to_string(),
+        };
+        vec![
+            StatusCell { text: format!("({x}, {y})"), weight: 1, align: Align::Left },
+            StatusCell { text: distances, weight: 2, align: Align::Center },
+            StatusCell { text: frame, weight: 1, align: Align::Right },
+        ]
+    }
 
-const fn text_to_bitmap<const N:usize>(text: &[u8;N]) -> Option<[[[bool; 5]; 8]; N]> {
-    let mut result = [[[false; 5]; 8]; N];
-    let mut i = 0;
-    while i < N {
-        let char = text[i];
-        if let Some(char_index) = index_u8(&LETTER_DATA, char) {
-            result[i] = FONT_DATA[char_index];
-        } else {
-            return None; // Invalid character
+    /// Logs which band(s) the cursor is over, for clicks
+    fn report_clicked_band(&self) {
+        let Some(cursor) = self.cursor else {
+            return;
+        };
+        let (window_x, window_y) = self.position.or_default();
+        let x = saturate(window_x as i64 + cursor.x.floor() as i64);
+        let y = saturate(window_y as i64 + cursor.y.floor() as i64);
+        let edges = bands_at(x, y, (self.monitor_size.width, self.monitor_size.height), self.render_options.boundary_size);
+        let names: Vec<_> = edges.iter().map(|edge| edge.name()).collect();
+        let display_y = self.render_options.origin.display_y(y, self.monitor_size.height as i32);
+        match names.as_slice() {
+            [] => debug!("Clicked at ({x}, {display_y}), outside the boundary"),
+            [name] => debug!("Clicked at ({x}, {display_y}) in the {name} band"),
+            _ => debug!("Clicked at ({x}, {display_y}) in the {} bands", names.join(" and ")),
+        }
+    }
+
+    /// Whether the window currently overlaps any boundary band
+    fn is_touching_boundary(&self) -> bool {
+        let Some(window) = &self.window else {
+            return false;
+        };
+        let size = window.inner_size();
+        let (x, y) = self.position.or_default();
+        let rect = Rect::new(x, y, size.width as i32, size.height as i32);
+        is_touching_boundary(rect, (self.monitor_size.width, self.monitor_size.height), self.render_options.boundary_size)
+    }
+    
+    /// Writes the render state as pretty JSON to `path`, or stdout for `-`
+    fn write_state(&self, path: &str) {
+        let Some(state) = self.render_state() else {
+            return;
+        };
+        let json = serde_json::to_string_pretty(&state).unwrap();
+        if path == "-" {
+            print_output(&format!("{json}\n"));
+        } else if let Err(err) = std::fs::write(path, json) {
+            warn!("Failed to write state to {path}: {err}");
         }
-        i+= 1;
     }
-    Some(result)
 }
 
-const fn index_u8<const N: usize>(arr: &[u8; N], element: u8) -> Option<usize> {
-    let l = arr.len();
-    let mut i = 0;
-    while i < l {
-        if arr[i] == element {
-            return Some(i);
+/// Moves the `size` frame drawn into `back_buffer` to the presented `buffer`: through
+/// `paletted` with `Buffering::Paletted`, then turned for a quarter-turn `rotation`.
+/// A frame drawn straight into `buffer` (`Direct`, upright) is left alone. A frame with
+/// more than 256 colors switches `buffering` to `BackBuffer` for good.
+fn present_back_buffer(
+    back_buffer: &mut [u32],
+    paletted: &mut PalettedBuffer,
+    buffering: &mut Buffering,
+    rotation: Rotation,
+    size: (u32, u32),
+    buffer: &mut [u32],
+) {
+    let (width, height) = size;
+    if *buffering == Buffering::Paletted {
+        if !paletted.index_from(width, height, back_buffer) {
+            warn!("Frame has more than 256 colors; presenting from the ARGB back buffer from now on");
+            *buffering = Buffering::BackBuffer;
+        } else if rotation.is_quarter_turn() {
+            // The palette's colors are what get turned
+            paletted.present(back_buffer);
+        } else {
+            paletted.present(buffer);
+            return;
         }
-        i += 1;
     }
-    None
+    if rotation.is_quarter_turn() {
+        rotate_frame(back_buffer, width, height, buffer, rotation);
+    } else if *buffering == Buffering::BackBuffer {
+        buffer.copy_from_slice(back_buffer);
+    }
 }
 
-const TEXT_BITMAPS: [[[bool; 5]; 8]; LEN] = text_to_bitmap(&TEXT_SOURCE).unwrap();
-
-const LETTER_DATA: [u8; 26 + 26 + 4] = [
-    // Uppercase letters A-Z
-    b'A', b'B', b'C', b'D', b'E', b'F', b'G', b'H', b'I', b'J', b'K', b'L', b'M',
-    b'N', b'O', b'P', b'Q', b'R', b'S', b'T', b'U', b'V', b'W', b'X', b'Y', b'Z',
-    // Lowercase letters a-z
-    b'a', b'b', b'c', b'd', b'e', b'f', b'g', b'h', b'i', b'j', b'k', b'l', b'm',
-    b'n', b'o', b'p', b'q', b'r', b's', b't', b'u', b'v', b'w', b'x', b'y', b'z',
-    // Special characters: space, underscore, curly braces
-    b'{', b'}', b'_', b' ',
-];
+/// Where `monitor` sits on the virtual desktop
+fn monitor_rect(monitor: &MonitorHandle) -> Rect {
+    let position = monitor.position();
+    let size = monitor.size();
+    Rect::new(position.x, position.y, size.width as i32, size.height as i32)
+}
 
-const FONT_DATA: [[[bool; 5]; 8];26+26+4] = [
-    // Uppercase letters
-    [
-        [false, true, true, true, false],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, true, true, true, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [false, false, false, false, false],
-    ],
-    [
-        [true, true, true, true, false],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, true, true, true, false],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, true, true, true, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, true, true, true, false],
-        [true, false, false, false, true],
-        [true, false, false, false, false],
-        [true, false, false, false, false],
-        [true, false, false, false, false],
-        [true, false, false, false, true],
-        [false, true, true, true, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [true, true, true, false, false],
-        [true, false, false, true, false],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, true, false],
-        [true, true, true, false, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [true, true, true, true, true],
-        [true, false, false, false, false],
-        [true, false, false, false, false],
-        [true, true, true, true, false],
-        [true, false, false, false, false],
-        [true, false, false, false, false],
-        [true, true, true, true, true],
-        [false, false, false, false, false],
-    ],
-    [
-        [true, true, true, true, true],
-        [true, false, false, false, false],
-        [true, false, false, false, false],
-        [true, true, true, true, false],
-        [true, false, false, false, false],
-        [true, false, false, false, false],
-        [true, false, false, false, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, true, true, true, false],
-        [true, false, false, false, true],
-        [true, false, false, false, false],
-        [true, false, true, true, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [false, true, true, true, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, true, true, true, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, true, true, true, false],
-        [false, false, true, false, false],
-        [false, false, true, false, false],
-        [false, false, true, false, false],
-        [false, false, true, false, false],
-        [false, false, true, false, false],
-        [false, true, true, true, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, false, false, false, true],
-        [false, false, false, false, true],
-        [false, false, false, false, true],
-        [false, false, false, false, true],
-        [false, false, false, false, true],
-        [true, false, false, false, true],
-        [false, true, true, true, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [true, false, false, false, true],
-        [true, false, false, true, false],
-        [true, false, true, false, false],
-        [true, true, false, false, false],
-        [true, false, true, false, false],
-        [true, false, false, true, false],
-        [true, false, false, false, true],
-        [false, false, false, false, false],
-    ],
-    [
-        [true, false, false, false, false],
-        [true, false, false, false, false],
-        [true, false, false, false, false],
-        [true, false, false, false, false],
-        [true, false, false, false, false],
-        [true, false, false, false, false],
-        [true, true, true, true, true],
-        [false, false, false, false, false],
-    ],
-    [
-        [true, false, false, false, true],
-        [true, true, false, true, true],
-        [true, false, true, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [false, false, false, false, false],
-    ],
-    [
-        [true, false, false, false, true],
-        [true, true, false, false, true],
-        [true, false, true, false, true],
-        [true, false, false, true, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, true, true, true, false],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [false, true, true, true, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [true, true, true, true, false],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, true, true, true, false],
-        [true, false, false, false, false],
-        [true, false, false, false, false],
-        [true, false, false, false, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, true, true, true, false],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, true, false, true],
-        [true, false, false, true, false],
-        [false, true, true, false, true],
-        [false, false, false, false, false],
-    ],
-    [
-        [true, true, true, true, false],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, true, true, true, false],
-        [true, false, true, false, false],
-        [true, false, false, true, false],
-        [true, false, false, false, true],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, true, true, true, false],
-        [true, false, false, false, true],
-        [true, false, false, false, false],
-        [false, true, true, true, false],
-        [false, false, false, false, true],
-        [true, false, false, false, true],
-        [false, true, true, true, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [true, true, true, true, true],
-        [false, false, true, false, false],
-        [false, false, true, false, false],
-        [false, false, true, false, false],
-        [false, false, true, false, false],
-        [false, false, true, false, false],
-        [false, false, true, false, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [false, true, true, true, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [false, true, false, true, false],
-        [false, true, false, true, false],
-        [false, false, true, false, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, true, false, true],
-        [true, false, true, false, true],
-        [true, true, false, true, true],
-        [true, false, false, false, true],
-        [false, false, false, false, false],
-    ],
-    [
-        [true, false, false, false, true],
-        [false, true, false, true, false],
-        [false, false, true, false, false],
-        [false, false, true, false, false],
-        [false, false, true, false, false],
-        [false, true, false, true, false],
-        [true, false, false, false, true],
-        [false, false, false, false, false],
-    ],
-    [
-        [true, false, false, false, true],
-        [false, true, false, true, false],
-        [false, false, true, false, false],
-        [false, false, true, false, false],
-        [false, false, true, false, false],
-        [false, false, true, false, false],
-        [false, false, true, false, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [true, true, true, true, true],
-        [false, false, false, false, true],
-        [false, false, false, true, false],
-        [false, false, true, false, false],
-        [false, true, false, false, false],
-        [true, false, false, false, false],
-        [true, true, true, true, true],
-        [false, false, false, false, false],
-    ],
-    
-    // Lowercase letters  
-    [
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [false, true, true, true, false],
-        [false, false, false, false, true],
-        [false, true, true, true, true],
-        [true, false, false, false, true],
-        [false, true, true, true, true],
-        [false, false, false, false, false],
-    ],
-    [
-        [true, false, false, false, false],
-        [true, false, false, false, false],
-        [true, true, true, true, false],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, true, true, true, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [false, true, true, true, false],
-        [true, false, false, false, true],
-        [true, false, false, false, false],
-        [true, false, false, false, true],
-        [false, true, true, true, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, false, false, false, true],
-        [false, false, false, false, true],
-        [false, true, true, true, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [false, true, true, true, true],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [false, true, true, true, false],
-        [true, false, false, false, true],
-        [true, true, true, true, true],
-        [true, false, false, false, false],
-        [false, true, true, true, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, false, true, true, false],
-        [false, true, false, false, true],
-        [false, true, false, false, false],
-        [true, true, true, false, false],
-        [false, true, false, false, false],
-        [false, true, false, false, false],
-        [false, true, false, false, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [false, true, true, true, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [false, true, true, true, true],
-        [false, false, false, false, true],
-        [false, true, true, true, false],
-    ],
-    [
-        [true, false, false, false, false],
-        [true, false, false, false, false],
-        [true, true, true, true, false],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, false, true, false, false],
-        [false, false, false, false, false],
-        [false, true, true, false, false],
-        [false, false, true, false, false],
-        [false, false, true, false, false],
-        [false, false, true, false, false],
-        [false, true, true, true, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, false, false, true, false],
-        [false, false, false, false, false],
-        [false, false, true, true, false],
-        [false, false, false, true, false],
-        [false, false, false, true, false],
-        [false, false, false, true, false],
-        [true, false, false, true, false],
-        [false, true, true, false, false],
-    ],
-    [
-        [true, false, false, false, false],
-        [true, false, false, false, false],
-        [true, false, false, true, false],
-        [true, false, true, false, false],
-        [true, true, false, false, false],
-        [true, false, true, false, false],
-        [true, false, false, true, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, true, true, false, false],
-        [false, false, true, false, false],
-        [false, false, true, false, false],
-        [false, false, true, false, false],
-        [false, false, true, false, false],
-        [false, false, true, false, false],
-        [false, true, true, true, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [true, true, false, true, false],
-        [true, false, true, false, true],
-        [true, false, true, false, true],
-        [true, false, true, false, true],
-        [true, false, true, false, true],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [true, true, true, true, false],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [false, true, true, true, false],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [false, true, true, true, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [true, true, true, true, false],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, true, true, true, false],
-        [true, false, false, false, false],
-        [true, false, false, false, false],
-    ],
-    [
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [false, true, true, true, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [false, true, true, true, true],
-        [false, false, false, false, true],
-        [false, false, false, false, true],
-    ],
-    [
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [true, false, true, true, false],
-        [true, true, false, false, true],
-        [true, false, false, false, false],
-        [true, false, false, false, false],
-        [true, false, false, false, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [false, true, true, true, false],
-        [true, false, false, false, false],
-        [false, true, true, true, false],
-        [false, false, false, false, true],
-        [true, true, true, true, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, true, false, false, false],
-        [false, true, false, false, false],
-        [true, true, true, false, false],
-        [false, true, false, false, false],
-        [false, true, false, false, false],
-        [false, true, false, false, true],
-        [false, false, true, true, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [false, true, true, true, true],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [false, true, false, true, false],
-        [false, true, false, true, false],
-        [false, false, true, false, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [true, false, false, false, true],
-        [true, false, true, false, true],
-        [true, false, true, false, true],
-        [true, false, true, false, true],
-        [false, true, false, true, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [true, false, false, false, true],
-        [false, true, false, true, false],
-        [false, false, true, false, false],
-        [false, true, false, true, false],
-        [true, false, false, false, true],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [true, false, false, false, true],
-        [false, true, true, true, true],
-        [false, false, false, false, true],
-        [false, true, true, true, false],
-    ],
-    [
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [true, true, true, true, true],
-        [false, false, false, true, false],
-        [false, false, true, false, false],
-        [false, true, false, false, false],
-        [true, true, true, true, true],
-        [false, false, false, false, false],
-    ],
-    
-    // Special characters
-    [
-        [false, false, true, true, false],
-        [false, true, false, false, false],
-        [false, true, false, false, false],
-        [true, false, false, false, false],
-        [false, true, false, false, false],
-        [false, true, false, false, false],
-        [false, false, true, true, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, true, true, false, false],
-        [false, false, false, true, false],
-        [false, false, false, true, false],
-        [false, false, false, false, true],
-        [false, false, false, true, false],
-        [false, false, false, true, false],
-        [false, true, true, false, false],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [true, true, true, true, true],
-        [false, false, false, false, false],
-    ],
-    [
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-        [false, false, false, false, false],
-    ],
-];
+/// Draws `--ime` text at the left of the window's vertical middle: the committed text,
+/// then the composition with its active segment (or all of it) underlined
+fn draw_ime_text(buffer: &mut [u32], width: u32, height: u32, ime: &ImeText, options: &RenderOptions) {
+    let font = &options.font;
+    let style = TextStyle { scale: 2, ..options.text };
+    let y = height as i32 / 2;
+    let x = draw_str(buffer, font, &ime.committed, IME_TEXT_MARGIN, y, width, &style);
+    if ime.preedit.is_empty() {
+        return;
+    }
+    draw_str(buffer, font, &ime.preedit, x, y, width, &style);
+    let offsets = glyph_offsets(font, &ime.preedit, &style);
+    let (start, end) = match ime.preedit_cursor {
+        Some((start, end)) if start != end => (start.min(end), start.max(end)),
+        _ => (0, ime.preedit.len()),
+    };
+    // The cursor is in bytes, the offsets per character
+    let char_index = |byte: usize| ime.preedit.get(..byte).map_or(offsets.len() - 1, |prefix| prefix.chars().count());
+    let (start, end) = (offsets[char_index(start)], offsets[char_index(end)]);
+    let bottom = y + measure_str(font, &ime.preedit, &style).1;
+    draw_underline(buffer, width, x + start, bottom, end - start - style.scale, style.scale, style.color);
+}
+
+/// Draws the cursor's world coordinates (in the configured origin) next to the cursor,
+/// flipped to the other side when it would run off the window
+fn draw_cursor_readout(
+    buffer: &mut [u32],
+    width: u32,
+    height: u32,
+    window_pos: (i32, i32),
+    cursor: PhysicalPosition<f64>,
+    monitor_height: u32,
+    options: &RenderOptions,
+) {
+    let (cursor_x, cursor_y) = (cursor.x.floor() as i32, cursor.y.floor() as i32);
+    let world_x = window_pos.0 as i64 + cursor_x as i64;
+    let world_y = options.origin.display_y(saturate(window_pos.1 as i64 + cursor_y as i64), saturate(monitor_height as i64));
+    let text = format!("({world_x}, {world_y})");
+    let style = TextStyle { scale: 2, ..options.text };
+    let (text_width, text_height) = measure_str(&options.font, &text, &style);
+    let mut x = cursor_x + CURSOR_READOUT_OFFSET;
+    let mut y = cursor_y + CURSOR_READOUT_OFFSET;
+    if x + text_width > width as i32 {
+        x = cursor_x - CURSOR_READOUT_OFFSET - text_width;
+    }
+    if y + text_height > height as i32 {
+        y = cursor_y - CURSOR_READOUT_OFFSET - text_height;
+    }
+    draw_str(buffer, &options.font, &text, x, y, width, &style);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(argv: &[&str]) -> Args {
+        Args::parse_from(argv.iter().map(|arg| arg.to_string()).collect())
+    }
+
+    #[test]
+    fn quiet_forces_overlays_off() {
+        let overlays = ["--compass", "--band-labels", "--show-edges", "--status-bar", "--trail", "5", "--safe-area", "10"];
+        let loud = parse(&overlays);
+        assert!(loud.compass && loud.band_labels && loud.show_edges && loud.status_bar);
+        assert_eq!((loud.trail_len, loud.safe_area_percent), (5, Some(10.0)));
+
+        // Wherever `--quiet` appears, and with the text content flags left alone
+        let quiet = parse(&[&["--quiet", "--shadow", "--label", "10,10,hi"], &overlays[..]].concat());
+        assert!(!(quiet.compass || quiet.band_labels || quiet.show_edges || quiet.status_bar || quiet.config.show_magnifier));
+        assert_eq!((quiet.trail_len, quiet.safe_area_percent), (0, None));
+        assert!(quiet.shadow);
+        assert_eq!(quiet.labels.len(), 1);
+    }
+
+    #[test]
+    fn help_lists_a_key_for_every_baked_text() {
+        assert!(BAKED_TEXT_KEYS.ends_with(&BAKED_TEXTS.len().to_string()));
+        assert!(help_text().lines().any(|line| line.starts_with(BAKED_TEXT_KEYS) && line.ends_with("pick the hidden text")));
+    }
+
+    #[test]
+    fn paletted_frames_are_indexed_before_a_quarter_turn() {
+        let mut app = App::new(parse(&["--paletted", "--rotate", "90"]));
+        assert_eq!((app.buffering, app.rotation), (Buffering::Paletted, Rotation::Cw90));
+        let (width, height) = (3, 2);
+        let frame = [0xFF000000, 0xFFFFFFFF, 0xFF000000, 0xFFFFFFFF, 0xFFFFFFFF, 0xFF000000];
+        let mut expected = [0; 6];
+        rotate_frame(&frame, width, height, &mut expected, Rotation::Cw90);
+
+        let mut back_buffer = frame.to_vec();
+        let mut buffer = [0; 6];
+        present_back_buffer(&mut back_buffer, &mut app.paletted, &mut app.buffering, app.rotation, (width, height), &mut buffer);
+        assert_eq!(buffer, expected);
+        assert_eq!((app.paletted.width(), app.paletted.height()), (width, height));
+        assert_eq!(app.buffering, Buffering::Paletted);
+
+        // Too many colors still falls back, and the ARGB frame is turned instead
+        let mut back_buffer: Vec<u32> = (0..300).map(|i| 0xFF000000 | i).collect();
+        let mut expected = vec![0; 300];
+        rotate_frame(&back_buffer, 30, 10, &mut expected, Rotation::Cw90);
+        let mut buffer = vec![0; 300];
+        present_back_buffer(&mut back_buffer, &mut app.paletted, &mut app.buffering, app.rotation, (30, 10), &mut buffer);
+        assert_eq!(buffer, expected);
+        assert_eq!(app.buffering, Buffering::BackBuffer);
+    }
+
+    /// Creating a real surface needs a display, so this only covers the window-less path:
+    /// repeated rebuilds fail cleanly and leave nothing half built
+    #[test]
+    fn rebuilding_the_surface_twice_without_a_window_fails_cleanly() {
+        let mut app = App::new(parse(&[]));
+        for _ in 0..2 {
+            assert!(matches!(app.rebuild_surface(), Err(AppError::NoWindow)));
+            assert!(app.context.is_none() && app.surface.is_none());
+        }
+    }
+}
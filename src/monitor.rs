@@ -0,0 +1,80 @@
+//! Choosing which monitor the window opens on, and facts about it
+
+use crate::geometry::Rect;
+
+/// Millimetres per inch
+const MM_PER_INCH: f64 = 25.4;
+
+/// Index of the first monitor whose name contains `query`, ignoring case. Monitors
+/// without a name never match.
+pub fn find_monitor_by_name<'a>(names: impl IntoIterator<Item = Option<&'a str>>, query: &str) -> Option<usize> {
+    let query = query.to_lowercase();
+    names
+        .into_iter()
+        .position(|name| name.is_some_and(|name| name.to_lowercase().contains(&query)))
+}
+
+/// Resolves mirrored displays, which report overlapping (usually identical) rects, to
+/// one of them: of the monitors in `rects` overlapping `rects[current]`, the one with
+/// the most pixels, then the lowest index. The answer is the same whichever of the
+/// mirrors the platform reports as current. Also returns whether any mirror was found.
+pub fn resolve_mirrored_monitor(rects: &[Rect], current: usize) -> (usize, bool) {
+    let Some(rect) = rects.get(current) else {
+        return (current, false);
+    };
+    let mirrors: Vec<usize> = (0..rects.len()).filter(|&i| rects[i].intersect(rect).is_some()).collect();
+    let chosen = mirrors
+        .iter()
+        .copied()
+        .min_by_key(|&i| (std::cmp::Reverse(rects[i].width as i64 * rects[i].height as i64), i))
+        .unwrap_or(current);
+    (chosen, mirrors.len() > 1)
+}
+
+/// Dots per inch along the diagonal of a monitor `size_px` pixels and `size_mm`
+/// millimetres across, `None` if the physical size is unknown or zero
+pub fn monitor_dpi(size_px: (u32, u32), size_mm: Option<(u32, u32)>) -> Option<f64> {
+    let (width_mm, height_mm) = size_mm?;
+    let diagonal_mm = (width_mm as f64).hypot(height_mm as f64);
+    if diagonal_mm == 0.0 {
+        return None;
+    }
+    let diagonal_px = (size_px.0 as f64).hypot(size_px.1 as f64);
+    Some(diagonal_px / (diagonal_mm / MM_PER_INCH))
+}
+
+/// The DPI readout, e.g. "DPI: 163 (scale 2)", or "DPI: n/a (scale 1.25)"
+pub fn format_dpi(dpi: Option<f64>, scale_factor: f64) -> String {
+    match dpi {
+        Some(dpi) => format!("DPI: {dpi:.0} (scale {scale_factor})"),
+        None => format!("DPI: n/a (scale {scale_factor})"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_mirrored_monitors_resolve_to_the_same_one() {
+        let mirror = Rect::new(0, 0, 1920, 1080);
+        let rects = [mirror, mirror, Rect::new(1920, 0, 1280, 1024)];
+        // Whichever mirror the platform calls current, the lowest index wins the tie
+        assert_eq!(resolve_mirrored_monitor(&rects, 0), (0, true));
+        assert_eq!(resolve_mirrored_monitor(&rects, 1), (0, true));
+        assert_eq!(resolve_mirrored_monitor(&rects, 2), (2, false));
+        // A bigger mirror beats a smaller one
+        let rects = [Rect::new(0, 0, 1280, 720), mirror];
+        assert_eq!(resolve_mirrored_monitor(&rects, 0), (1, true));
+    }
+
+    #[test]
+    fn monitor_names_match_case_insensitive_substrings() {
+        let names = [Some("Built-in Retina Display"), None, Some("DELL U2720Q"), Some("Dell P2419H")];
+        assert_eq!(find_monitor_by_name(names, "u2720"), Some(2));
+        // First match wins
+        assert_eq!(find_monitor_by_name(names, "dell"), Some(2));
+        assert_eq!(find_monitor_by_name(names, "RETINA"), Some(0));
+        assert_eq!(find_monitor_by_name(names, "LG"), None);
+    }
+}
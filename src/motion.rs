@@ -0,0 +1,216 @@
+//! Window motion: how fast it is being dragged, for `--motion-thickness`, and moving it
+//! smoothly with held arrow keys
+//!
+//! Speeds come from successive `Moved` positions and their timestamps, smoothed with an
+//! exponential moving average so a single jumpy event doesn't make the bands flicker.
+
+use std::time::{Duration, Instant};
+
+/// Weight of the newest sample in the moving average
+const SMOOTHING: f32 = 0.3;
+
+/// With no move for this long the window counts as at rest again
+pub const MOTION_TIMEOUT: Duration = Duration::from_millis(150);
+
+/// Extra band thickness per pixel per second of speed
+const THICKNESS_PER_SPEED: f32 = 0.05;
+
+/// Most the bands can grow by, however fast the drag
+pub const MAX_EXTRA_THICKNESS: i32 = 200;
+
+/// Smoothed window speed in pixels per second
+#[derive(Debug, Default, Clone, Copy)]
+pub struct VelocityTracker {
+    last: Option<((i32, i32), Instant)>,
+    speed: f32,
+}
+
+impl VelocityTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that the window was at `position` at `now`
+    pub fn record(&mut self, position: (i32, i32), now: Instant) {
+        if let Some((previous, at)) = self.last {
+            let elapsed = now.saturating_duration_since(at);
+            if elapsed > MOTION_TIMEOUT {
+                self.speed = 0.0;
+            } else if !elapsed.is_zero() {
+                let distance = ((position.0 - previous.0) as f32).hypot((position.1 - previous.1) as f32);
+                let sample = distance / elapsed.as_secs_f32();
+                self.speed += SMOOTHING * (sample - self.speed);
+            }
+        }
+        self.last = Some((position, now));
+    }
+
+    /// Smoothed speed at `now`; zero once the window has been still for `MOTION_TIMEOUT`
+    pub fn speed(&self, now: Instant) -> f32 {
+        match self.last {
+            Some((_, at)) if now.saturating_duration_since(at) <= MOTION_TIMEOUT => self.speed,
+            _ => 0.0,
+        }
+    }
+
+    /// When the window will count as at rest, if it's currently moving
+    pub fn settles_at(&self) -> Option<Instant> {
+        self.last.filter(|_| self.speed > 0.0).map(|(_, at)| at + MOTION_TIMEOUT)
+    }
+
+    /// Drops the speed to zero if the window has come to rest by `now`; true if it just
+    /// did, so the thicker bands need redrawing
+    pub fn settle(&mut self, now: Instant) -> bool {
+        if self.settles_at().is_some_and(|at| now >= at) {
+            self.speed = 0.0;
+            return true;
+        }
+        false
+    }
+}
+
+/// Extra band thickness for a window moving at `speed` pixels per second
+pub fn motion_thickness(speed: f32) -> i32 {
+    ((speed * THICKNESS_PER_SPEED).round() as i32).clamp(0, MAX_EXTRA_THICKNESS)
+}
+
+/// Speed of arrow-key movement, in pixels per second
+pub const KEY_MOVE_SPEED: f64 = 600.0;
+
+/// How often the window moves while an arrow key is held
+pub const KEY_MOVE_INTERVAL: Duration = Duration::from_millis(16);
+
+/// Longest gap one step makes up for, so a stalled loop doesn't jump the window
+const MAX_KEY_STEP: Duration = Duration::from_millis(100);
+
+/// An arrow key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// Which arrow keys are down, for moving the window at a steady speed instead of at the
+/// OS key-repeat rate
+#[derive(Debug, Default, Clone, Copy)]
+pub struct HeldKeys {
+    left: bool,
+    right: bool,
+    up: bool,
+    down: bool,
+    /// When the window last moved; `None` while no key is held
+    last_step: Option<Instant>,
+    /// Sub-pixel movement carried over to the next step
+    remainder: (f64, f64),
+}
+
+impl HeldKeys {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Marks `direction` as held or released at `now`
+    pub fn set(&mut self, direction: Direction, held: bool, now: Instant) {
+        match direction {
+            Direction::Left => self.left = held,
+            Direction::Right => self.right = held,
+            Direction::Up => self.up = held,
+            Direction::Down => self.down = held,
+        }
+        if !self.is_held() {
+            *self = Self::default();
+        } else if self.last_step.is_none() {
+            self.last_step = Some(now);
+        }
+    }
+
+    pub const fn is_held(&self) -> bool {
+        self.left || self.right || self.up || self.down
+    }
+
+    /// -1, 0 or 1 along each axis; opposite keys cancel out
+    pub const fn direction(&self) -> (i32, i32) {
+        (self.right as i32 - self.left as i32, self.down as i32 - self.up as i32)
+    }
+
+    /// Whole pixels to move by at `now` since the last step
+    pub fn step(&mut self, now: Instant) -> (i32, i32) {
+        let Some(last) = self.last_step else {
+            return (0, 0);
+        };
+        let elapsed = now.saturating_duration_since(last).min(MAX_KEY_STEP);
+        let (dx, dy) = key_displacement(self.direction(), elapsed);
+        let (x, y) = (dx + self.remainder.0, dy + self.remainder.1);
+        self.remainder = (x.fract(), y.fract());
+        self.last_step = Some(now);
+        (x.trunc() as i32, y.trunc() as i32)
+    }
+
+    /// When the next step is due, while a key is held
+    pub fn next_step_at(&self) -> Option<Instant> {
+        self.last_step.map(|last| last + KEY_MOVE_INTERVAL)
+    }
+}
+
+/// Distance covered in `elapsed` moving in `direction` at `KEY_MOVE_SPEED`
+pub fn key_displacement(direction: (i32, i32), elapsed: Duration) -> (f64, f64) {
+    let distance = KEY_MOVE_SPEED * elapsed.as_secs_f64();
+    (direction.0 as f64 * distance, direction.1 as f64 * distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dragging_faster_makes_the_bands_thicker() {
+        let start = Instant::now();
+        let tick = Duration::from_millis(10);
+        let mut tracker = VelocityTracker::new();
+        // 10px every 10ms is 1000px/s; the average only gets there gradually
+        let mut thicknesses = Vec::new();
+        for i in 0..4 {
+            tracker.record((10 * i, 0), start + tick * i as u32);
+            thicknesses.push(motion_thickness(tracker.speed(start + tick * i as u32)));
+        }
+        assert_eq!(thicknesses, [0, 15, 26, 33]);
+        // Standing still for the timeout drops back to the plain bands
+        let still = start + tick * 3 + MOTION_TIMEOUT + Duration::from_millis(1);
+        assert_eq!(motion_thickness(tracker.speed(still)), 0);
+        assert!(tracker.settle(still));
+
+        // Thickness is clamped at both ends
+        assert_eq!(motion_thickness(-50.0), 0);
+        assert_eq!(motion_thickness(1_000_000.0), MAX_EXTRA_THICKNESS);
+    }
+
+    #[test]
+    fn held_keys_move_at_a_steady_speed() {
+        let start = Instant::now();
+        let mut keys = HeldKeys::new();
+        assert_eq!(keys.step(start), (0, 0));
+        keys.set(Direction::Right, true, start);
+        keys.set(Direction::Up, true, start);
+        assert_eq!(keys.direction(), (1, -1));
+        // 600px/s for 50ms is 30px along each held axis
+        assert_eq!(keys.step(start + Duration::from_millis(50)), (30, -30));
+        // Fractions of a pixel carry over: 1ms is 0.6px, so two of them make one pixel
+        let now = start + Duration::from_millis(50);
+        assert_eq!(keys.step(now + Duration::from_millis(1)), (0, 0));
+        assert_eq!(keys.step(now + Duration::from_millis(2)), (1, -1));
+        // A stalled loop doesn't jump the window
+        assert_eq!(keys.step(now + Duration::from_secs(5)), (60, -60));
+
+        // Opposite keys cancel, and releasing everything stops the stepping
+        keys.set(Direction::Left, true, now);
+        assert_eq!(keys.direction(), (0, -1));
+        for direction in [Direction::Left, Direction::Right, Direction::Up] {
+            keys.set(direction, false, now);
+        }
+        assert!(!keys.is_held());
+        assert_eq!(keys.next_step_at(), None);
+        assert_eq!(keys.step(now + Duration::from_secs(6)), (0, 0));
+    }
+}
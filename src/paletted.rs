@@ -0,0 +1,154 @@
+//! An 8-bit palette-indexed frame buffer, expanded to `0xAARRGGBB` when presented
+//!
+//! Worth it for frames built from a handful of flat colors (bands, background, text) at
+//! high resolutions: clears and fills write a quarter of the bytes, the buffer is a
+//! quarter of the size, and recoloring a whole frame is a palette write. It doesn't fit
+//! anything that needs arbitrary colors, such as gamma-correct blending, trails or
+//! pattern images with more than 256 colors; draw those into a direct ARGB buffer. The
+//! expansion in `present` costs one lookup per pixel, so a frame that's redrawn entirely
+//! every time gains little over writing ARGB directly.
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+
+/// Pixels as indices into a 256-entry palette
+#[derive(Debug, Clone)]
+pub struct PalettedBuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+    /// Color for each index; unused entries are opaque black
+    pub palette: [u32; 256],
+}
+
+impl PalettedBuffer {
+    /// A `width` x `height` buffer filled with index 0
+    pub fn new(width: u32, height: u32, palette: [u32; 256]) -> Self {
+        Self { width, height, pixels: vec![0; (width * height) as usize], palette }
+    }
+
+    /// A palette with `colors` at the first indices and opaque black after them, `None`
+    /// if there are more than 256 of them
+    pub fn palette_from(colors: &[u32]) -> Option<[u32; 256]> {
+        let mut palette = [0xFF000000; 256];
+        palette.get_mut(..colors.len())?.copy_from_slice(colors);
+        Some(palette)
+    }
+
+    /// Replaces the contents with `src`, a `width` x `height` ARGB frame, and the palette
+    /// with its colors in order of first appearance. Returns `false` if `src` has more
+    /// than 256 colors, in which case the pixels are left part-written and the palette
+    /// as it was.
+    pub fn index_from(&mut self, width: u32, height: u32, src: &[u32]) -> bool {
+        self.resize(width, height);
+        let mut palette = [0xFF000000; 256];
+        let mut indices: HashMap<u32, u8> = HashMap::new();
+        // Frames are mostly long runs of one color, so most pixels skip the map
+        let mut last = None;
+        for (out, &color) in self.pixels.iter_mut().zip(src) {
+            let index = match last {
+                Some((last_color, index)) if last_color == color => index,
+                _ => {
+                    let next = indices.len();
+                    match indices.entry(color) {
+                        Entry::Occupied(entry) => *entry.get(),
+                        Entry::Vacant(_) if next == palette.len() => return false,
+                        Entry::Vacant(entry) => {
+                            palette[next] = color;
+                            *entry.insert(next as u8)
+                        }
+                    }
+                }
+            };
+            last = Some((color, index));
+            *out = index;
+        }
+        self.palette = palette;
+        true
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Changes the size, clearing every pixel to index 0
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.width = width;
+        self.height = height;
+        self.pixels.clear();
+        self.pixels.resize((width * height) as usize, 0);
+    }
+
+    pub fn fill(&mut self, index: u8) {
+        self.pixels.fill(index);
+    }
+
+    /// Sets the pixel at `(x, y)`; out-of-bounds writes are ignored
+    pub fn set(&mut self, x: i32, y: i32, index: u8) {
+        if x >= 0 && y >= 0 && (x as u32) < self.width && (y as u32) < self.height {
+            self.pixels[(y as u32 * self.width + x as u32) as usize] = index;
+        }
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Option<u8> {
+        (x < self.width && y < self.height).then(|| self.pixels[(y * self.width + x) as usize])
+    }
+
+    /// Fills the part of the `width` x `height` rectangle at `(x, y)` inside the buffer
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: i32, height: i32, index: u8) {
+        let x0 = x.clamp(0, self.width as i32) as usize;
+        let x1 = (x + width).clamp(0, self.width as i32) as usize;
+        for row in y.max(0)..(y + height).min(self.height as i32) {
+            let start = row as usize * self.width as usize;
+            self.pixels[start + x0..start + x1.max(x0)].fill(index);
+        }
+    }
+
+    /// Expands every pixel through the palette into `dst`, which must hold exactly
+    /// `width * height` pixels (e.g. the softbuffer buffer)
+    pub fn present(&self, dst: &mut [u32]) {
+        debug_assert_eq!(dst.len(), self.pixels.len(), "present target size doesn't match the buffer");
+        for (out, &index) in dst.iter_mut().zip(&self.pixels) {
+            *out = self.palette[index as usize];
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indexed_pixels_round_trip_through_the_palette() {
+        let palette = PalettedBuffer::palette_from(&[0xFF000000, 0xFF00FF00, 0xFFFFFFFF]).unwrap();
+        let mut buffer = PalettedBuffer::new(3, 2, palette);
+        buffer.set(0, 0, 1);
+        buffer.set(2, 1, 2);
+        buffer.set(5, 5, 2);
+        let mut out = vec![0; 6];
+        buffer.present(&mut out);
+        assert_eq!(out, [0xFF00FF00, 0xFF000000, 0xFF000000, 0xFF000000, 0xFF000000, 0xFFFFFFFF]);
+
+        // Packing that frame again gives the same pixels back
+        let mut packed = PalettedBuffer::new(0, 0, [0; 256]);
+        assert!(packed.index_from(3, 2, &out));
+        assert_eq!(packed.get(0, 0), Some(0));
+        let mut again = vec![0; 6];
+        packed.present(&mut again);
+        assert_eq!(again, out);
+    }
+
+    #[test]
+    fn more_than_256_colors_are_refused() {
+        let colors: Vec<u32> = (0..257).map(|i| 0xFF000000 | i).collect();
+        assert!(PalettedBuffer::palette_from(&colors[..256]).is_some());
+        assert!(PalettedBuffer::palette_from(&colors).is_none());
+        let mut buffer = PalettedBuffer::new(0, 0, [0; 256]);
+        assert!(!buffer.index_from(257, 1, &colors));
+        assert!(buffer.index_from(256, 1, &colors[..256]));
+    }
+}
@@ -0,0 +1,103 @@
+//! Repeating fills for the boundary bands
+
+use std::io;
+
+/// Pattern pixels (row-major, `0xAARRGGBB`) with their width and height
+pub type Pattern = (Vec<u32>, u32, u32);
+
+/// The pattern pixel covering `(world_x, world_y)` when the pattern is tiled from the
+/// world origin, so it stays fixed to the monitor as the window moves
+pub fn pattern_pixel((pixels, width, height): &Pattern, world_x: i64, world_y: i64) -> u32 {
+    let x = world_x.rem_euclid(*width as i64) as usize;
+    let y = world_y.rem_euclid(*height as i64) as usize;
+    pixels[y * *width as usize + x]
+}
+
+/// Stripe color of the built-in `stripes` pattern
+pub const HAZARD_YELLOW: u32 = 0xFFFFCC00;
+
+/// Diagonal hazard stripes of `color` and black, `stripe` pixels wide
+pub fn hazard_stripes(color: u32, stripe: u32) -> Pattern {
+    let size = stripe * 2;
+    let pixels = (0..size * size)
+        .map(|i| if ((i % size) + (i / size)) % size < stripe { color } else { 0xFF000000 })
+        .collect();
+    (pixels, size, size)
+}
+
+/// Loads a binary PPM (`P6`, 8 bits per channel) image as a pattern
+pub fn load_ppm(path: &str) -> io::Result<Pattern> {
+    parse_ppm(&std::fs::read(path)?).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not an 8-bit binary PPM (P6) image"))
+}
+
+/// Parses the bytes of a binary PPM, `None` if they aren't one
+pub fn parse_ppm(data: &[u8]) -> Option<Pattern> {
+    let mut pos = 0;
+    // The header is four whitespace-separated fields, with `#` comments to end of line
+    let mut fields = [0u32; 3];
+    let mut magic = None;
+    for field in 0..4 {
+        loop {
+            match data.get(pos)? {
+                b'#' => while *data.get(pos)? != b'\n' {
+                    pos += 1;
+                },
+                byte if byte.is_ascii_whitespace() => pos += 1,
+                _ => break,
+            }
+        }
+        let start = pos;
+        while data.get(pos).is_some_and(|byte| !byte.is_ascii_whitespace()) {
+            pos += 1;
+        }
+        let token = std::str::from_utf8(&data[start..pos]).ok()?;
+        if field == 0 {
+            magic = Some(token);
+        } else {
+            fields[field - 1] = token.parse().ok()?;
+        }
+    }
+    // Exactly one whitespace byte separates the header from the pixels
+    pos += 1;
+    let [width, height, max] = fields;
+    if magic != Some("P6") || max != 255 || width == 0 || height == 0 {
+        return None;
+    }
+    // Checked, since a header can claim any size up to u32::MAX by u32::MAX
+    let len = (width as usize).checked_mul(height as usize)?.checked_mul(3)?;
+    let rgb = data.get(pos..pos.checked_add(len)?)?;
+    let pixels = rgb
+        .chunks_exact(3)
+        .map(|p| 0xFF000000 | (p[0] as u32) << 16 | (p[1] as u32) << 8 | p[2] as u32)
+        .collect();
+    Some((pixels, width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{BOUNDARY_COLOR, RenderOptions, render_frame};
+
+    #[test]
+    fn band_pixels_follow_the_world_tiled_pattern() {
+        // 2x2 checker of four distinct colors
+        let pattern: Pattern = (vec![0xFF000001, 0xFF000002, 0xFF000003, 0xFF000004], 2, 2);
+        let options = RenderOptions { band_pattern: Some(pattern.clone()), ..RenderOptions::default() };
+        let mut buffer = vec![0; 20 * 20];
+        // Top-left corner at (5, 7), inside both the left and top bands
+        render_frame(&mut buffer, 20, 20, (5, 7), (1920, 1080), &options);
+        // Window (0, 0) is world (5, 7): pattern (1, 1)
+        assert_eq!(buffer[0], 0xFF000004);
+        // Window (2, 1) is world (7, 8): pattern (1, 0)
+        assert_eq!(buffer[20 + 2], 0xFF000002);
+        assert_eq!(pattern_pixel(&pattern, -1, -2), 0xFF000002);
+        assert!(!buffer.contains(&BOUNDARY_COLOR));
+    }
+
+    #[test]
+    fn ppm_with_overflowing_size_is_rejected() {
+        assert_eq!(parse_ppm(b"P6 4294967295 4294967295 255\n\0\0\0"), None);
+        assert_eq!(parse_ppm(b"P6 2 1 255\n\x01\x02\x03\x04\x05"), None);
+        assert_eq!(parse_ppm(b"P6 1 1 255\n\x01\x02\x03"), Some((vec![0xFF010203], 1, 1)));
+    }
+}
@@ -0,0 +1,86 @@
+//! Frame time collection and summary statistics
+
+use std::fmt::Write;
+use std::time::Duration;
+
+/// Upper bound on stored samples so long sessions don't grow without limit
+pub const MAX_SAMPLES: usize = 100_000;
+
+/// Histogram bucket width in milliseconds
+const BUCKET_MS: u128 = 2;
+const BUCKETS: usize = 10;
+
+/// Collected frame render times
+#[derive(Debug, Default)]
+pub struct FrameStats {
+    samples: Vec<Duration>,
+    /// Samples discarded after `MAX_SAMPLES` was reached
+    dropped: usize,
+}
+
+impl FrameStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, frame_time: Duration) {
+        if self.samples.len() < MAX_SAMPLES {
+            self.samples.push(frame_time);
+        } else {
+            self.dropped += 1;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// The `p`th percentile (0-100) using the nearest-rank method
+    pub fn percentile(&self, p: f64) -> Option<Duration> {
+        let mut sorted = self.samples.clone();
+        sorted.sort_unstable();
+        percentile(&sorted, p)
+    }
+
+    /// A multi-line summary with p50/p95/p99 and a text histogram
+    pub fn report(&self) -> String {
+        let mut sorted = self.samples.clone();
+        sorted.sort_unstable();
+        let mut out = String::new();
+        let (Some(p50), Some(p95), Some(p99)) = (percentile(&sorted, 50.0), percentile(&sorted, 95.0), percentile(&sorted, 99.0)) else {
+            return "No frames recorded\n".to_string();
+        };
+        // Writing into a String cannot fail
+        let _ = writeln!(out, "Frames: {} ({} dropped)", sorted.len(), self.dropped);
+        let _ = writeln!(out, "p50: {p50:?}  p95: {p95:?}  p99: {p99:?}  max: {:?}", sorted[sorted.len() - 1]);
+
+        let mut counts = [0usize; BUCKETS];
+        for sample in &sorted {
+            let bucket = (sample.as_millis() / BUCKET_MS).min(BUCKETS as u128 - 1) as usize;
+            counts[bucket] += 1;
+        }
+        let most = counts.iter().copied().max().unwrap_or(1).max(1);
+        for (i, count) in counts.iter().enumerate() {
+            let label = if i == BUCKETS - 1 {
+                format!("{:>3}+ ms", i as u128 * BUCKET_MS)
+            } else {
+                format!("{:>3}-{:<3}ms", i as u128 * BUCKET_MS, (i as u128 + 1) * BUCKET_MS)
+            };
+            let _ = writeln!(out, "{label} | {:<40} {count}", "#".repeat(count * 40 / most));
+        }
+        out
+    }
+}
+
+/// Nearest-rank percentile of already sorted samples
+pub fn percentile(sorted: &[Duration], p: f64) -> Option<Duration> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
@@ -0,0 +1,80 @@
+//! Loading PC Screen Font (PSF1 and PSF2) console fonts
+
+use std::collections::HashMap;
+use std::io;
+
+use crate::font::Font;
+
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+const PSF1_MODE_512: u8 = 0x01;
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xb5, 0x4a, 0x86];
+
+/// Loads a PSF1 or PSF2 font. Glyph `i` is used for character U+00`i`, which matches
+/// ASCII (and often Latin-1) for the usual console fonts; any Unicode table is ignored,
+/// as are glyphs past 255.
+pub fn load_psf(path: &str) -> io::Result<Font> {
+    parse_psf(&std::fs::read(path)?).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not a PSF1 or PSF2 font"))
+}
+
+/// Parses the bytes of a PSF1 or PSF2 font, `None` if they aren't one
+pub fn parse_psf(data: &[u8]) -> Option<Font> {
+    if data.starts_with(&PSF1_MAGIC) {
+        let mode = *data.get(2)?;
+        let height = *data.get(3)? as usize;
+        let count = if mode & PSF1_MODE_512 != 0 { 512 } else { 256 };
+        glyphs_from(data.get(4..)?, count, 8, height, height)
+    } else if data.starts_with(&PSF2_MAGIC) {
+        let field = |index: usize| -> Option<usize> {
+            let bytes = data.get(4 + index * 4..8 + index * 4)?;
+            Some(u32::from_le_bytes(bytes.try_into().ok()?) as usize)
+        };
+        // Fields after the magic: version, header size, flags, count, bytes per glyph,
+        // height, width
+        let (header_size, count, bytes_per_glyph, height, width) = (field(1)?, field(3)?, field(4)?, field(5)?, field(6)?);
+        glyphs_from(data.get(header_size..)?, count, width, height, bytes_per_glyph)
+    } else {
+        None
+    }
+}
+
+/// Unpacks `count` glyphs of `bytes_per_glyph` bytes each, with rows padded to whole
+/// bytes and the leftmost pixel in the most significant bit
+fn glyphs_from(data: &[u8], count: usize, width: usize, height: usize, bytes_per_glyph: usize) -> Option<Font> {
+    let row_bytes = width.div_ceil(8);
+    if width == 0 || height == 0 || bytes_per_glyph < row_bytes * height {
+        return None;
+    }
+    let mut glyphs = HashMap::new();
+    for index in 0..count.min(256) {
+        let glyph = data.get(index * bytes_per_glyph..(index + 1) * bytes_per_glyph)?;
+        let pixels = (0..height)
+            .flat_map(|row| (0..width).map(move |col| glyph[row * row_bytes + col / 8] & (0x80 >> (col % 8)) != 0))
+            .collect();
+        glyphs.insert(char::from(index as u8), pixels);
+    }
+    // The cells of console fonts already include their spacing
+    Some(Font::new(width, height, 0, glyphs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::TEXT_COLOR;
+    use crate::text::{TextStyle, draw_char};
+
+    #[test]
+    fn psf1_glyph_loads_and_renders() {
+        // 256 blank 8x4 glyphs, except 'A' which is a diagonal from the top-left
+        let mut data = vec![0x36, 0x04, 0x00, 4];
+        data.resize(4 + 256 * 4, 0);
+        data[4 + b'A' as usize * 4..][..4].copy_from_slice(&[0x80, 0x40, 0x20, 0x10]);
+        let font = parse_psf(&data).unwrap();
+        assert_eq!((font.cell_width, font.cell_height, font.glyphs.len()), (8, 4, 256));
+
+        let mut buffer = vec![0; 8 * 4];
+        draw_char(&mut buffer, 0, 0, &font, 'A', 8, &TextStyle { scale: 1, ..TextStyle::default() });
+        let lit: Vec<usize> = (0..buffer.len()).filter(|&i| buffer[i] == TEXT_COLOR).collect();
+        assert_eq!(lit, [0, 8 + 1, 16 + 2, 24 + 3]);
+        assert!(parse_psf(&data[..100]).is_none());
+    }
+}
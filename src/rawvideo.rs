@@ -0,0 +1,51 @@
+//! Raw video output for piping frames into ffmpeg
+//!
+//! Every frame is written as packed RGBA8 (`rgba` in ffmpeg's terms): 4 bytes per pixel,
+//! rows top to bottom, no header and no row padding. Every frame has the same size, set
+//! up front, so a window that is resized is cropped or padded to it. For
+//! `--raw-out 1280x720`:
+//!
+//! ```text
+//! window-messing --raw-out 1280x720 | ffmpeg -f rawvideo -pix_fmt rgba -video_size 1280x720 -framerate 60 -i - out.mp4
+//! ```
+//!
+//! Frames are only written when the window redraws, so `-framerate` is nominal.
+
+use std::io::{self, Write};
+
+use crate::color::argb_to_rgba;
+
+/// Converts frames to fixed-size RGBA and writes them out
+#[derive(Debug)]
+pub struct RawVideoWriter {
+    width: u32,
+    height: u32,
+    /// Fill for the part of a frame the presented buffer doesn't cover
+    background: u32,
+    bytes: Vec<u8>,
+}
+
+impl RawVideoWriter {
+    pub fn new(width: u32, height: u32, background: u32) -> Self {
+        Self { width, height, background, bytes: Vec::with_capacity((width * height * 4) as usize) }
+    }
+
+    /// Writes `pixels` (`pixels_width` wide, `0xAARRGGBB`) as one frame, anchored
+    /// top-left, and flushes so the reader gets it straight away
+    pub fn write_frame(&mut self, out: &mut impl Write, pixels: &[u32], pixels_width: u32) -> io::Result<()> {
+        let pixels_height = pixels.len() as u32 / pixels_width.max(1);
+        self.bytes.clear();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let color = if x < pixels_width && y < pixels_height {
+                    pixels[(y * pixels_width + x) as usize]
+                } else {
+                    self.background
+                };
+                self.bytes.extend_from_slice(&argb_to_rgba(color));
+            }
+        }
+        out.write_all(&self.bytes)?;
+        out.flush()
+    }
+}
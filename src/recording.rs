@@ -0,0 +1,187 @@
+//! The text format shared by `--record` and `--replay`: one window position per line as
+//! `MILLIS X Y`, where `MILLIS` counts from the start of the recording. Blank lines and
+//! lines starting with `#` are ignored.
+
+use std::io::{self, BufWriter, Write};
+use std::time::{Duration, Instant};
+
+/// A window position at a point in a recording
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordedPosition {
+    /// Time since the recording started
+    pub at: Duration,
+    pub x: i32,
+    pub y: i32,
+}
+
+/// One line of the format, without the newline
+pub fn format_position(position: &RecordedPosition) -> String {
+    format!("{} {} {}", position.at.as_millis(), position.x, position.y)
+}
+
+/// Parses a whole recording. Positions must be in time order; the error names the first
+/// offending line.
+pub fn parse_positions(text: &str) -> Result<Vec<RecordedPosition>, String> {
+    let mut positions: Vec<RecordedPosition> = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        let (Some(millis), Some(x), Some(y), None) = (fields.next(), fields.next(), fields.next(), fields.next()) else {
+            return Err(format!("line {}: expected MILLIS X Y", i + 1));
+        };
+        let (Ok(millis), Ok(x), Ok(y)) = (millis.parse(), x.parse(), y.parse()) else {
+            return Err(format!("line {}: expected MILLIS X Y as integers", i + 1));
+        };
+        let position = RecordedPosition { at: Duration::from_millis(millis), x, y };
+        if positions.last().is_some_and(|last| last.at > position.at) {
+            return Err(format!("line {}: time goes backwards", i + 1));
+        }
+        positions.push(position);
+    }
+    Ok(positions)
+}
+
+/// Plays a recording back against the wall clock
+#[derive(Debug, Clone)]
+pub struct Replay {
+    positions: Vec<RecordedPosition>,
+    next: usize,
+    started: Option<Instant>,
+}
+
+impl Replay {
+    pub fn new(positions: Vec<RecordedPosition>) -> Self {
+        Self { positions, next: 0, started: None }
+    }
+
+    /// Starts the clock at `now`; later calls keep the original start
+    pub fn start(&mut self, now: Instant) {
+        self.started.get_or_insert(now);
+    }
+
+    /// The most recent position due by `now` that hasn't been returned yet. Positions
+    /// that fell due together are skipped in favour of the last, since only where the
+    /// window ends up matters.
+    pub fn take_due(&mut self, now: Instant) -> Option<RecordedPosition> {
+        let start = self.started?;
+        let due = self.positions[self.next..]
+            .iter()
+            .take_while(|position| start + position.at <= now)
+            .count();
+        self.next += due;
+        (due > 0).then(|| self.positions[self.next - 1])
+    }
+
+    /// When the next position falls due, `None` before `start` or once finished
+    pub fn next_at(&self) -> Option<Instant> {
+        Some(self.started? + self.positions.get(self.next)?.at)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next == self.positions.len()
+    }
+}
+
+/// How often a `Recorder` flushes buffered lines
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Writes positions in the format `parse_positions` reads, timed from when it was created
+#[derive(Debug)]
+pub struct Recorder<W: Write> {
+    out: BufWriter<W>,
+    start: Instant,
+    last_flush: Instant,
+}
+
+impl<W: Write> Recorder<W> {
+    /// Starts a recording at `start`, writing a header comment to `out`
+    pub fn new(out: W, start: Instant) -> io::Result<Self> {
+        let mut out = BufWriter::new(out);
+        writeln!(out, "# window-messing recording: MILLIS X Y")?;
+        Ok(Self { out, start, last_flush: start })
+    }
+
+    /// Appends the window position at `now`, flushing if the last flush was a while ago
+    pub fn record(&mut self, now: Instant, x: i32, y: i32) -> io::Result<()> {
+        let position = RecordedPosition { at: now.saturating_duration_since(self.start), x, y };
+        writeln!(self.out, "{}", format_position(&position))?;
+        if now.saturating_duration_since(self.last_flush) >= FLUSH_INTERVAL {
+            self.out.flush()?;
+            self.last_flush = now;
+        }
+        Ok(())
+    }
+
+    /// Flushes everything and hands back the underlying writer
+    pub fn finish(self) -> io::Result<W> {
+        self.out.into_inner().map_err(|err| err.into_error())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// A writer whose output can be inspected while a `Recorder` still owns it
+    #[derive(Clone, Default)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn position_file_parses_and_reports_bad_lines() {
+        let text = "# comment\n\n0 10 20\n  250 -5 7  \n250 0 0\n";
+        let positions = parse_positions(text).unwrap();
+        assert_eq!(
+            positions,
+            [
+                RecordedPosition { at: Duration::ZERO, x: 10, y: 20 },
+                RecordedPosition { at: Duration::from_millis(250), x: -5, y: 7 },
+                RecordedPosition { at: Duration::from_millis(250), x: 0, y: 0 },
+            ]
+        );
+        assert_eq!(parse_positions("0 1\n"), Err("line 1: expected MILLIS X Y".to_string()));
+        assert_eq!(parse_positions("0 1 2 3\n"), Err("line 1: expected MILLIS X Y".to_string()));
+        assert_eq!(parse_positions("# x\n0 a 2\n"), Err("line 2: expected MILLIS X Y as integers".to_string()));
+        assert_eq!(parse_positions("10 0 0\n5 0 0\n"), Err("line 2: time goes backwards".to_string()));
+    }
+
+    #[test]
+    fn recording_round_trips_through_the_parser() {
+        let start = Instant::now();
+        let moves = [(0, 100, 200), (16, 101, 199), (1500, -40, 3000), (1516, -40, 3001)];
+        let mut recorder = Recorder::new(Vec::new(), start).unwrap();
+        for &(millis, x, y) in &moves {
+            recorder.record(start + Duration::from_millis(millis), x, y).unwrap();
+        }
+        let text = String::from_utf8(recorder.finish().unwrap()).unwrap();
+        let expected: Vec<_> = moves.iter().map(|&(millis, x, y)| RecordedPosition { at: Duration::from_millis(millis), x, y }).collect();
+        assert_eq!(parse_positions(&text).unwrap(), expected);
+    }
+
+    #[test]
+    fn queued_write_is_flushed_on_finish() {
+        let start = Instant::now();
+        let out = Shared::default();
+        let mut recorder = Recorder::new(out.clone(), start).unwrap();
+        recorder.record(start + Duration::from_millis(10), 5, 6).unwrap();
+        // Well within the flush interval, so the line is still buffered
+        assert!(out.0.borrow().is_empty());
+        recorder.finish().unwrap();
+        let text = String::from_utf8(out.0.borrow().clone()).unwrap();
+        assert!(text.ends_with("\n10 5 6\n"), "{text:?}");
+    }
+}
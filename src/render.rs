@@ -0,0 +1,1867 @@
+//! Frame rendering and drawing primitives, independent of any windowing backend
+
+use crate::blockcode::BlockCode;
+use crate::color::{bayer_offset, blend_pixel, lerp_color, lerp_color_dithered, parse_color, scale_brightness};
+use std::sync::Arc;
+
+use crate::font::{BAKED_TEXTS, BUILTIN_FONT, BakedText, Font, LATIN1_GLYPHS, LETTER_DATA};
+use crate::geometry::{Edge, Origin, Rect, direction_to_origin, edge_distance, saturate, visible_bands, world_to_window};
+use crate::magnifier::{INSET_SIZE, ZOOM, draw_magnifier, magnifier_source};
+use crate::pattern::{Pattern, pattern_pixel};
+use crate::text::{
+    ADVANCE, HEIGHT, TextStyle, draw_char, draw_str, draw_str_vertical, draw_text, measure_str, measure_str_vertical, measure_text,
+};
+
+/// Default distance from each monitor edge within which pixels are drawn green
+pub const BOUNDARY_SIZE: i32 = 100;
+/// Largest accepted boundary size; anything wider would cover any realistic monitor
+pub const MAX_BOUNDARY_SIZE: i32 = 4096;
+
+/// Environment variable that overrides the boundary size
+pub const BOUNDARY_ENV_VAR: &str = "WINDOW_MESSING_BOUNDARY";
+
+pub const BACKGROUND: u32 = 0xFF000000; // Black
+pub const BOUNDARY_COLOR: u32 = 0xFF00FF00; // Green
+pub const TEXT_COLOR: u32 = 0xFFFFFFFF; // White
+pub const LETTERBOX_COLOR: u32 = 0xFF202020; // Dark grey, to tell the bars from the background
+
+/// A set of colors for the boundary view
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorScheme {
+    pub name: &'static str,
+    pub background: u32,
+    pub boundary: u32,
+    pub text: u32,
+}
+
+impl ColorScheme {
+    /// Sets the background, flat band and hidden text colors of `options`
+    pub fn apply(&self, options: &mut RenderOptions) {
+        options.background = self.background;
+        options.boundary_color = self.boundary;
+        options.text.color = self.text;
+    }
+}
+
+/// Built-in schemes, cycled through at runtime; the first is the default look
+pub const PRESETS: &[ColorScheme] = &[
+    ColorScheme { name: "green on black", background: BACKGROUND, boundary: BOUNDARY_COLOR, text: TEXT_COLOR },
+    ColorScheme { name: "amber on black", background: 0xFF000000, boundary: 0xFFFFB000, text: 0xFFFFE0A0 },
+    ColorScheme { name: "white on blue", background: 0xFF0000A0, boundary: 0xFFFFFFFF, text: 0xFFFFFF00 },
+    ColorScheme { name: "high contrast", background: 0xFF000000, boundary: 0xFFFFFF00, text: 0xFFFFFFFF },
+];
+
+/// Dark boundaries on a light background, used instead of the default look when
+/// following a light system theme
+pub const LIGHT_SCHEME: ColorScheme =
+    ColorScheme { name: "green on white", background: 0xFFF0F0F0, boundary: 0xFF007000, text: 0xFF202020 };
+
+/// Scheme matching the system theme: `LIGHT_SCHEME`, or the default look for dark themes
+pub const fn scheme_for_theme(light: bool) -> &'static ColorScheme {
+    if light { &LIGHT_SCHEME } else { &PRESETS[0] }
+}
+
+/// Index into `PRESETS` of the scheme after `index`, wrapping back to the first
+pub const fn next_preset(index: usize) -> usize {
+    (index + 1) % PRESETS.len()
+}
+
+/// Proximity mode color for a window just entering a band
+pub const PROXIMITY_FAR_COLOR: u32 = 0xFFFFFF00; // Yellow
+/// Proximity mode color for a window touching the monitor edge
+pub const PROXIMITY_NEAR_COLOR: u32 = 0xFFFF0000; // Red
+
+/// How boundary bands are colored
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BoundaryMode {
+    /// Every band is `RenderOptions::boundary_color`
+    #[default]
+    Flat,
+    /// Each band is shaded from yellow to red by how close the window is to that edge
+    Proximity,
+    /// Bands fade from `RenderOptions::boundary_color` at the monitor edge to the
+    /// background at their inner edge
+    Gradient,
+    /// No bands; only the pixels on the monitor's outermost rows and columns, to check
+    /// a window sits exactly on an edge
+    EdgeOnly,
+}
+
+impl BoundaryMode {
+    /// The name `parse` accepts
+    pub const fn name(self) -> &'static str {
+        match self {
+            BoundaryMode::Flat => "flat",
+            BoundaryMode::Proximity => "proximity",
+            BoundaryMode::Gradient => "gradient",
+            BoundaryMode::EdgeOnly => "edge-only",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "flat" => Some(BoundaryMode::Flat),
+            "proximity" => Some(BoundaryMode::Proximity),
+            "gradient" => Some(BoundaryMode::Gradient),
+            "edge-only" => Some(BoundaryMode::EdgeOnly),
+            _ => None,
+        }
+    }
+}
+
+/// Flat color of one band for the current frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BandStyle {
+    /// How close the window is to this edge, 0 (just entering the band) to 1 (at the
+    /// edge); decides which band wins where two overlap
+    pub proximity: f32,
+    pub color: u32,
+}
+
+/// How far into the band a window edge `distance` pixels from the monitor edge is:
+/// 0 at the inner edge of the band, 1 at (or past) the monitor edge
+pub fn proximity_ratio(distance: i32, boundary_size: i32) -> f32 {
+    if boundary_size <= 0 {
+        return if distance <= 0 { 1.0 } else { 0.0 };
+    }
+    (1.0 - distance as f32 / boundary_size as f32).clamp(0.0, 1.0)
+}
+
+/// Yellow for a ratio of 0 through to red at 1
+pub fn proximity_color(ratio: f32) -> u32 {
+    lerp_color(PROXIMITY_FAR_COLOR, PROXIMITY_NEAR_COLOR, ratio)
+}
+
+/// The color the band along `edge` is drawn with this frame
+pub fn band_style(mode: BoundaryMode, flat_color: u32, edge: Edge, window: Rect, monitor_size: (u32, u32), boundary_size: i32) -> BandStyle {
+    match mode {
+        BoundaryMode::Flat | BoundaryMode::Gradient | BoundaryMode::EdgeOnly => BandStyle { proximity: 0.0, color: flat_color },
+        BoundaryMode::Proximity => {
+            let proximity = proximity_ratio(edge_distance(edge, window, monitor_size), boundary_size);
+            BandStyle { proximity, color: proximity_color(proximity) }
+        }
+    }
+}
+
+/// Color of a band pixel `depth` inside the monitor in `BoundaryMode::Gradient`, from
+/// `outer` at the monitor edge to `inner` at the inner edge of the band. With `dither`,
+/// an ordered dither at the world pixel hides the steps between 8-bit shades.
+pub fn gradient_color(outer: u32, inner: u32, depth: i64, boundary_size: i32, dither: Option<(i64, i64)>) -> u32 {
+    // Sample at the pixel centre so the first and last pixels aren't pure `outer`/`inner`
+    let t = if boundary_size > 0 { (depth as f32 + 0.5) / boundary_size as f32 } else { 0.0 };
+    let offset = dither.map_or(0.0, |(x, y)| bayer_offset(x, y));
+    lerp_color_dithered(outer, inner, t, offset)
+}
+
+/// Whichever of two overlapping bands is closer to its edge
+fn more_urgent(current: Option<BandStyle>, candidate: BandStyle) -> Option<BandStyle> {
+    match current {
+        Some(style) if style.proximity >= candidate.proximity => Some(style),
+        _ => Some(candidate),
+    }
+}
+
+/// Glyph scale used for the hidden text at a scale factor of 1.0
+pub const BASE_TEXT_SCALE: i32 = 3;
+
+/// A piece of runtime text drawn on top of every frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    pub text: String,
+    /// Window coordinates, or world coordinates when `pinned`
+    pub x: i32,
+    pub y: i32,
+    pub style: TextStyle,
+    /// Stay put on the desktop while the window moves over it, showing only where the
+    /// window overlaps it
+    pub pinned: bool,
+}
+
+impl Label {
+    /// Where the label is drawn in a window whose top-left is at `window_pos`
+    pub const fn window_position(&self, window_pos: (i32, i32)) -> (i32, i32) {
+        if self.pinned { world_to_window((self.x, self.y), window_pos) } else { (self.x, self.y) }
+    }
+}
+
+/// An outlined, labelled rectangle for annotating screenshots
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedRegion {
+    /// Window coordinates, or world coordinates when `pinned`
+    pub rect: Rect,
+    pub label: String,
+    pub color: u32,
+    /// Stay put on the desktop while the window moves over it, like a pinned `Label`
+    pub pinned: bool,
+}
+
+/// Space between a region's outline and its label, in pixels
+const REGION_LABEL_INSET: i32 = 3;
+
+/// Outlines `region` in its color with its label just inside the top-left corner, for a
+/// window whose top-left is at `window_pos`
+pub fn draw_region(buffer: &mut [u32], width: u32, height: u32, region: &NamedRegion, window_pos: (i32, i32), font: &Font) {
+    let rect = if region.pinned { region.rect.relative_to(window_pos) } else { region.rect };
+    if rect.is_empty() {
+        return;
+    }
+    let (right, bottom) = (rect.right() - 1, rect.bottom() - 1);
+    draw_line(buffer, width, height, (rect.x, rect.y), (right, rect.y), region.color);
+    draw_line(buffer, width, height, (rect.x, bottom), (right, bottom), region.color);
+    draw_line(buffer, width, height, (rect.x, rect.y), (rect.x, bottom), region.color);
+    draw_line(buffer, width, height, (right, rect.y), (right, bottom), region.color);
+    let style = TextStyle { scale: 2, color: region.color, ..TextStyle::default() };
+    draw_str(buffer, font, &region.label, rect.x + REGION_LABEL_INSET, rect.y + REGION_LABEL_INSET, width, &style);
+}
+
+/// Knobs that change how a frame is drawn, independent of window geometry
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    /// Width of each boundary band in pixels
+    pub boundary_size: i32,
+    /// Style of the hidden text
+    pub text: TextStyle,
+    /// Convention for coordinates reported to the user
+    pub origin: Origin,
+    /// Extra text drawn over the boundary view
+    pub labels: Vec<Label>,
+    /// Show a zoomed inset of the pixels at the nearest monitor edge
+    pub show_magnifier: bool,
+    /// How the boundary bands are colored
+    pub boundary_mode: BoundaryMode,
+    /// Multiplier applied to boundary and text colors, e.g. to dim an unfocused window
+    pub brightness: f32,
+    /// Draw at `1 / render_scale` resolution and upscale with nearest-neighbour (1 = full).
+    ///
+    /// The per-pixel work shrinks by `render_scale²`, which helps on very high resolution
+    /// displays, but band edges and text snap to `render_scale`-pixel blocks and small
+    /// text loses detail (its scale is divided too, down to a minimum of 1).
+    pub render_scale: u32,
+    /// Only draw this many glyphs of the hidden text, for a typewriter reveal
+    pub reveal_chars: Option<usize>,
+    /// Index into `BAKED_TEXTS` of the string shown as the hidden text
+    pub baked_text: usize,
+    /// Tile this over the bands, anchored to the world origin, instead of their flat color
+    pub band_pattern: Option<Pattern>,
+    /// Draw an arrow in the top-right corner pointing towards the world origin
+    pub show_compass: bool,
+    /// Name each band the window overlaps ("TOP EDGE", ...) with text inside the band,
+    /// vertical for the left and right bands
+    pub band_labels: bool,
+    /// Fill behind everything
+    pub background: u32,
+    /// Band color in `BoundaryMode::Flat`
+    pub boundary_color: u32,
+    /// Concentric `(threshold, color)` frames drawn instead of the single band, sorted by
+    /// threshold. A pixel within `threshold` of any monitor edge is in that frame and
+    /// takes the color of the outermost (smallest threshold) frame it's in.
+    pub frames: Vec<(i32, u32)>,
+    /// Font for the labels and other runtime text; the hidden text is always baked in the
+    /// built-in font
+    pub font: Arc<Font>,
+    /// Round the inner corners where bands meet by carving out a quarter circle of this
+    /// radius (0 = square corners). Costs a distance test for every band pixel.
+    pub corner_radius: u32,
+    /// Outline the part of the window a capture card's overscan won't crop, inset by this
+    /// percentage of the window size on each side
+    pub safe_area_percent: Option<f32>,
+    /// Apply a 4x4 ordered dither to `BoundaryMode::Gradient` to hide banding
+    pub dither: bool,
+    /// Color every pixel by its distance to the nearest monitor edge instead of drawing
+    /// bands
+    pub heatmap: bool,
+    /// Confine everything to the largest centred area of this width / height ratio and
+    /// fill the rest with `letterbox_color`
+    pub target_aspect: Option<f32>,
+    pub letterbox_color: u32,
+    /// Show the hidden text as a grid of blocks instead of glyphs; see `blockcode`
+    pub block_code: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            boundary_size: BOUNDARY_SIZE,
+            text: TextStyle::default(),
+            origin: Origin::default(),
+            labels: Vec::new(),
+            show_magnifier: false,
+            boundary_mode: BoundaryMode::default(),
+            brightness: 1.0,
+            render_scale: 1,
+            reveal_chars: None,
+            baked_text: 0,
+            band_pattern: None,
+            show_compass: false,
+            band_labels: false,
+            background: BACKGROUND,
+            boundary_color: BOUNDARY_COLOR,
+            corner_radius: 0,
+            font: Arc::new(Font::builtin()),
+            frames: Vec::new(),
+            safe_area_percent: None,
+            dither: false,
+            heatmap: false,
+            target_aspect: None,
+            letterbox_color: LETTERBOX_COLOR,
+            block_code: false,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// The baked string drawn as the hidden text; the flag if `baked_text` is out of range
+    pub fn hidden_text(&self) -> &'static BakedText {
+        BAKED_TEXTS.get(self.baked_text).unwrap_or(&BAKED_TEXTS[0])
+    }
+}
+
+/// Restricts a requested boundary size to `0..=MAX_BOUNDARY_SIZE`
+pub fn clamp_boundary_size(size: i32) -> i32 {
+    size.clamp(0, MAX_BOUNDARY_SIZE)
+}
+
+/// Parses a boundary size such as the value of `WINDOW_MESSING_BOUNDARY`, clamped to the
+/// accepted range. Returns `None` if it isn't an integer.
+pub fn parse_boundary_size(value: &str) -> Option<i32> {
+    value.trim().parse().ok().map(clamp_boundary_size)
+}
+
+/// Parses `--frames` values such as `50:ff0000,100:ffff00` (threshold in pixels, then an
+/// RRGGBB color) into frames sorted by threshold. Returns `None` on any malformed entry.
+pub fn parse_frames(value: &str) -> Option<Vec<(i32, u32)>> {
+    let mut frames = value
+        .split(',')
+        .map(|entry| {
+            let (threshold, color) = entry.trim().split_once(':')?;
+            let threshold = clamp_boundary_size(threshold.parse().ok()?);
+            Some((threshold, parse_color(color)?))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    frames.sort_by_key(|&(threshold, _)| threshold);
+    Some(frames)
+}
+
+/// How far inside the monitor a world pixel is: its distance to the nearest edge, in
+/// whole pixels, negative once past an edge. The pixel is within `t` of an edge, in the
+/// half-plane sense the bands use, exactly when this is below `t`.
+pub fn edge_depth(world_x: i64, world_y: i64, monitor_size: (u32, u32)) -> i64 {
+    let right = monitor_size.0 as i64 - 1 - world_x;
+    let bottom = monitor_size.1 as i64 - 1 - world_y;
+    world_x.min(world_y).min(right).min(bottom)
+}
+
+/// Color of the outermost of `frames` (sorted by threshold) that a pixel `depth` inside
+/// the monitor falls in
+pub fn frame_color(frames: &[(i32, u32)], depth: i64) -> Option<u32> {
+    frames.iter().find(|&&(threshold, _)| depth < threshold as i64).map(|&(_, color)| color)
+}
+
+/// Converts a (possibly fractional) display scale factor into an integer glyph scale.
+///
+/// Glyphs can only be drawn at whole-pixel block sizes, so the scaled size is rounded to
+/// the nearest integer: 1.0 -> 3, 1.25 -> 4, 1.5 -> 5, 2.0 -> 6.
+pub fn text_scale_for(scale_factor: f64) -> i32 {
+    ((BASE_TEXT_SCALE as f64 * scale_factor).round() as i32).max(1)
+}
+
+/// The glyph scale to draw with: `text_scale_for(scale_factor)`, or `BASE_TEXT_SCALE`
+/// whatever the display scaling when `raw_pixels` asks for device-pixel exact output.
+/// Bands are always sized in physical pixels, so this is the only DPI adjustment.
+pub fn effective_text_scale(scale_factor: f64, raw_pixels: bool) -> i32 {
+    if raw_pixels { BASE_TEXT_SCALE } else { text_scale_for(scale_factor) }
+}
+
+/// Renders one frame of the boundary view for a window of `width` x `height`
+/// whose top-left corner sits at `window_pos` on a monitor of `monitor_size`
+pub fn render_frame(
+    buffer: &mut [u32],
+    width: u32,
+    height: u32,
+    window_pos: (i32, i32),
+    monitor_size: (u32, u32),
+    options: &RenderOptions,
+) {
+    if let Some(aspect) = options.target_aspect {
+        let viewport = Viewport::letterbox(width, height, aspect);
+        if viewport.rect != Rect::new(0, 0, width as i32, height as i32) {
+            render_letterboxed(buffer, width, viewport, window_pos, monitor_size, options);
+            return;
+        }
+    }
+    if options.render_scale > 1 {
+        render_frame_reduced(buffer, width, height, window_pos, monitor_size, options);
+        return;
+    }
+    
+    // Fill with the background color
+    buffer.fill(options.background);
+    
+    let window = Rect::new(window_pos.0, window_pos.1, width as i32, height as i32);
+    paint_bands(buffer, width, window, Rect::new(0, 0, width as i32, height as i32), monitor_size, options);
+    draw_overlays(buffer, width, height, window, monitor_size, options);
+}
+
+/// Sets the pixels inside `area` (window coordinates) that lie on the monitor's first or
+/// last row or column to `color`; nothing if the window doesn't reach one
+fn paint_edge_pixels(buffer: &mut [u32], width: u32, window: Rect, area: Rect, monitor_size: (u32, u32), color: u32) {
+    let (columns, rows) = monitor_edge_lines(window.width as u32, window.height as u32, (window.x, window.y), monitor_size);
+    let (x_range, y_range) = (area.x..area.right(), area.y..area.bottom());
+    for y in rows.into_iter().filter(|y| y_range.contains(y)) {
+        let row = y as usize * width as usize;
+        buffer[row + area.x as usize..row + area.right() as usize].fill(color);
+    }
+    for x in columns.into_iter().filter(|x| x_range.contains(x)) {
+        for y in y_range.clone() {
+            buffer[y as usize * width as usize + x as usize] = color;
+        }
+    }
+}
+
+/// Paints the band pixels inside `area` (window coordinates), leaving the rest of `area`
+/// untouched
+fn paint_bands(buffer: &mut [u32], width: u32, window: Rect, area: Rect, monitor_size: (u32, u32), options: &RenderOptions) {
+    if options.heatmap {
+        paint_heatmap(buffer, width, window, area, monitor_size, options.brightness);
+        return;
+    }
+    if !options.frames.is_empty() {
+        paint_frames(buffer, width, window, area, monitor_size, options);
+        return;
+    }
+    if options.boundary_mode == BoundaryMode::EdgeOnly {
+        paint_edge_pixels(buffer, width, window, area, monitor_size, scale_brightness(options.boundary_color, options.brightness));
+        return;
+    }
+    // World math is in i64 so huge monitors and far-off windows can't overflow
+    let (pos_x, pos_y) = (window.x as i64, window.y as i64);
+    let monitor_width = monitor_size.0 as i64;
+    let monitor_height = monitor_size.1 as i64;
+    let boundary_size = options.boundary_size as i64;
+    let styles = Edge::ALL.map(|edge| {
+        let style = band_style(options.boundary_mode, options.boundary_color, edge, window, monitor_size, options.boundary_size);
+        BandStyle { color: scale_brightness(style.color, options.brightness), ..style }
+    });
+    
+    // Draw boundaries where appropriate; where two overlap the one nearer its edge wins
+    for y in area.y..area.bottom() {
+        for x in area.x..area.right() {
+            let idx = (y as u32 * width + x as u32) as usize;
+            let mut band = None;
+            
+            // Calculate world coordinates for this pixel
+            let world_x = pos_x + x as i64;
+            let world_y = pos_y + y as i64;
+            
+            // Left boundary (world x < boundary_size)
+            if world_x < boundary_size {
+                band = more_urgent(band, styles[Edge::Left as usize]);
+            }
+            
+            // Right boundary (world x >= monitor_width - boundary_size)
+            if world_x >= monitor_width - boundary_size {
+                band = more_urgent(band, styles[Edge::Right as usize]);
+            }
+            
+            // Top boundary (world y < boundary_size)
+            if world_y < boundary_size {
+                band = more_urgent(band, styles[Edge::Top as usize]);
+            }
+            
+            // Bottom boundary (world y >= monitor_height - boundary_size)
+            if world_y >= monitor_height - boundary_size {
+                band = more_urgent(band, styles[Edge::Bottom as usize]);
+            }
+            
+            if options.corner_radius > 0 && in_rounded_corner(world_x, world_y, monitor_size, options.boundary_size, options.corner_radius) {
+                band = None;
+            }
+            
+            if let Some(style) = band {
+                buffer[idx] = match &options.band_pattern {
+                    Some(pattern) => scale_brightness(pattern_pixel(pattern, world_x, world_y), options.brightness),
+                    None if options.boundary_mode == BoundaryMode::Gradient => {
+                        let depth = edge_depth(world_x, world_y, monitor_size);
+                        let dither = options.dither.then_some((world_x, world_y));
+                        gradient_color(style.color, options.background, depth, options.boundary_size, dither)
+                    }
+                    None => style.color,
+                };
+            }
+        }
+    }
+}
+
+/// `paint_bands` for concentric frames
+fn paint_frames(buffer: &mut [u32], width: u32, window: Rect, area: Rect, monitor_size: (u32, u32), options: &RenderOptions) {
+    let frames: Vec<_> = options
+        .frames
+        .iter()
+        .map(|&(threshold, color)| (threshold, scale_brightness(color, options.brightness)))
+        .collect();
+    for y in area.y..area.bottom() {
+        for x in area.x..area.right() {
+            let depth = edge_depth(window.x as i64 + x as i64, window.y as i64 + y as i64, monitor_size);
+            if let Some(color) = frame_color(&frames, depth) {
+                buffer[(y as u32 * width + x as u32) as usize] = color;
+            }
+        }
+    }
+}
+
+/// Stops of the heatmap colormap, a coarse approximation of Turbo from cool (0) to hot (1)
+const HEATMAP_STOPS: [(f32, u32); 7] = [
+    (0.0, 0xFF30123B),
+    (0.15, 0xFF4686FB),
+    (0.35, 0xFF1AE4B6),
+    (0.5, 0xFFA2FC3C),
+    (0.65, 0xFFFABA39),
+    (0.85, 0xFFE4460A),
+    (1.0, 0xFF7A0403),
+];
+
+/// Heatmap color for a pixel `depth` inside a monitor of `monitor_size`: hottest at (or
+/// past) the edge, coolest at the centre
+pub fn heatmap_color(depth: i64, monitor_size: (u32, u32)) -> u32 {
+    // Deepest a pixel can be: half the shorter side
+    let max_depth = (monitor_size.0.min(monitor_size.1) / 2).max(1) as f32;
+    let heat = 1.0 - (depth as f32 / max_depth).clamp(0.0, 1.0);
+    let upper = HEATMAP_STOPS.iter().position(|&(stop, _)| stop >= heat).unwrap_or(HEATMAP_STOPS.len() - 1).max(1);
+    let (from_stop, from) = HEATMAP_STOPS[upper - 1];
+    let (to_stop, to) = HEATMAP_STOPS[upper];
+    lerp_color(from, to, (heat - from_stop) / (to_stop - from_stop))
+}
+
+/// `paint_bands` for the heatmap: every pixel of `area`, colored by its distance to the
+/// nearest monitor edge
+fn paint_heatmap(buffer: &mut [u32], width: u32, window: Rect, area: Rect, monitor_size: (u32, u32), brightness: f32) {
+    for y in area.y..area.bottom() {
+        for x in area.x..area.right() {
+            let depth = edge_depth(window.x as i64 + x as i64, window.y as i64 + y as i64, monitor_size);
+            buffer[(y as u32 * width + x as u32) as usize] = scale_brightness(heatmap_color(depth, monitor_size), brightness);
+        }
+    }
+}
+
+/// Whether the world pixel lies where two perpendicular bands overlap and within
+/// `radius` of the inner corner they form, i.e. in the quarter circle carved out of it
+pub fn in_rounded_corner(world_x: i64, world_y: i64, monitor_size: (u32, u32), boundary_size: i32, radius: u32) -> bool {
+    let boundary_size = boundary_size as i64;
+    let inner = |p: i64, extent: u32| {
+        if p < boundary_size {
+            Some(boundary_size)
+        } else if p >= extent as i64 - boundary_size {
+            Some(extent as i64 - boundary_size)
+        } else {
+            None
+        }
+    };
+    let (Some(corner_x), Some(corner_y)) = (inner(world_x, monitor_size.0), inner(world_y, monitor_size.1)) else {
+        return false;
+    };
+    // Doubled coordinates so the test is against the pixel centre in integers
+    let dx = 2 * world_x + 1 - 2 * corner_x;
+    let dy = 2 * world_y + 1 - 2 * corner_y;
+    let diameter = 2 * radius as i64;
+    // Also keeps the squares below from overflowing far from the corner
+    if dx.abs() >= diameter || dy.abs() >= diameter {
+        return false;
+    }
+    dx * dx + dy * dy < diameter * diameter
+}
+
+/// World position of the top-left of the hidden text
+pub const fn hidden_text_origin(monitor_size: (u32, u32)) -> (i32, i32) {
+    // Way off screen above the monitor, horizontally centered
+    ((monitor_size.0 / 2) as i32, saturate(-(monitor_size.1 as i64) - 1000))
+}
+
+/// World rect covered by a `len`-glyph hidden text drawn at `scale` in fixed-width
+/// cells, i.e. where a window has to be to reveal it
+pub const fn hidden_text_rect(monitor_size: (u32, u32), len: usize, scale: i32) -> Rect {
+    let (x, y) = hidden_text_origin(monitor_size);
+    let (width, height) = measure_text(len, scale);
+    Rect::new(x, y, width, height)
+}
+
+/// Whether a window can show all of a `len`-glyph hidden text at `scale` at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextFit {
+    Fits,
+    /// Only with a bigger window, at least `needed`
+    TooSmall { needed: (i32, i32) },
+    /// Not even with a window as big as the monitor
+    Never { needed: (i32, i32) },
+}
+
+/// How the hidden text fits a `window_size` window; windows can't grow past
+/// `monitor_size`, so text bigger than that can never be read in one piece
+pub fn hidden_text_fit(window_size: (u32, u32), monitor_size: (u32, u32), len: usize, scale: i32) -> TextFit {
+    let needed = measure_text(len, scale);
+    let fits = |size: (u32, u32)| needed.0 as i64 <= size.0 as i64 && needed.1 as i64 <= size.1 as i64;
+    if fits(window_size) {
+        TextFit::Fits
+    } else if fits(monitor_size) {
+        TextFit::TooSmall { needed }
+    } else {
+        TextFit::Never { needed }
+    }
+}
+
+/// Draws everything that sits on top of the bands: the hidden text, the labels and the
+/// magnifier
+fn draw_overlays(buffer: &mut [u32], width: u32, height: u32, window: Rect, monitor_size: (u32, u32), options: &RenderOptions) {
+    // Convert off-screen coordinates to window coordinates
+    let (text_x, text_y) = world_to_window(hidden_text_origin(monitor_size), (window.x, window.y));
+    
+    
+    let text_style = TextStyle { color: scale_brightness(options.text.color, options.brightness), ..options.text };
+    if options.block_code {
+        let text = options.hidden_text();
+        let colors = (text_style.color, scale_brightness(options.background, options.brightness));
+        let shown = options.reveal_chars.unwrap_or(text.source.len());
+        BlockCode::encode(text.source).draw(buffer, width, height, (text_x, text_y), shown, colors);
+    } else {
+        draw_text(buffer, options.hidden_text(), text_x, text_y, width, &text_style, options.reveal_chars);
+    }
+    
+    for label in &options.labels {
+        let style = TextStyle { color: scale_brightness(label.style.color, options.brightness), ..label.style };
+        let (x, y) = label.window_position((window.x, window.y));
+        draw_str(buffer, &options.font, &label.text, x, y, width, &style);
+    }
+    
+    if options.band_labels {
+        draw_band_labels(buffer, width, window, monitor_size, options);
+    }
+    
+    if options.show_compass {
+        let color = scale_brightness(TEXT_COLOR, options.brightness);
+        draw_compass(buffer, width, height, direction_to_origin((window.x, window.y)), color);
+    }
+    
+    if let Some(percent) = options.safe_area_percent {
+        draw_safe_area(buffer, width, height, percent, scale_brightness(SAFE_AREA_COLOR, options.brightness));
+    }
+    
+    // Must come last so the inset samples the finished frame
+    if options.show_magnifier {
+        let source = magnifier_source(window, monitor_size, INSET_SIZE / ZOOM);
+        draw_magnifier(buffer, width, height, source);
+    }
+}
+
+/// Text of the label for the band along `edge`, e.g. "TOP EDGE"
+pub fn band_label(edge: Edge) -> String {
+    format!("{} EDGE", edge.name().to_uppercase())
+}
+
+/// World position of the top-left of a `text_size` label for the band along `edge`,
+/// where `visible` is the part of the band in the window (from `visible_bands`). It's
+/// centred across the band so it stays near the monitor edge, and along the band on the
+/// visible part so it's in view wherever the window is, keeping out of the corners where
+/// bands cross when any of the visible part is between them.
+pub fn band_label_position(edge: Edge, visible: Rect, monitor_size: (u32, u32), boundary_size: i32, text_size: (i32, i32)) -> (i32, i32) {
+    let (text_width, text_height) = (text_size.0 as i64, text_size.1 as i64);
+    let between_corners = match edge {
+        Edge::Left | Edge::Right => Rect::new(visible.x, boundary_size, visible.width, saturate(monitor_size.1 as i64 - 2 * boundary_size as i64)),
+        Edge::Top | Edge::Bottom => Rect::new(boundary_size, visible.y, saturate(monitor_size.0 as i64 - 2 * boundary_size as i64), visible.height),
+    };
+    let visible = visible.intersect(&between_corners).unwrap_or(visible);
+    let boundary_size = boundary_size as i64;
+    let across = |band_start: i64, extent: i64| saturate(band_start + (boundary_size - extent) / 2);
+    let along_x = saturate(visible.x as i64 + (visible.width as i64 - text_width) / 2);
+    let along_y = saturate(visible.y as i64 + (visible.height as i64 - text_height) / 2);
+    match edge {
+        Edge::Left => (across(0, text_width), along_y),
+        Edge::Right => (across(monitor_size.0 as i64 - boundary_size, text_width), along_y),
+        Edge::Top => (along_x, across(0, text_height)),
+        Edge::Bottom => (along_x, across(monitor_size.1 as i64 - boundary_size, text_height)),
+    }
+}
+
+/// Draws `band_label` inside each band the window overlaps
+fn draw_band_labels(buffer: &mut [u32], width: u32, window: Rect, monitor_size: (u32, u32), options: &RenderOptions) {
+    let font = &options.font;
+    let style = TextStyle {
+        // A step smaller than the hidden text
+        scale: (options.text.scale - 1).max(1),
+        color: scale_brightness(options.text.color, options.brightness),
+        ..options.text
+    };
+    for (edge, visible) in visible_bands(window, monitor_size, options.boundary_size) {
+        let text = band_label(edge);
+        let vertical = matches!(edge, Edge::Left | Edge::Right);
+        let size = if vertical { measure_str_vertical(font, &text, &style) } else { measure_str(font, &text, &style) };
+        let world = band_label_position(edge, visible, monitor_size, options.boundary_size, size);
+        let (x, y) = world_to_window(world, (window.x, window.y));
+        if vertical {
+            draw_str_vertical(buffer, font, &text, x, y, width, &style);
+        } else {
+            draw_str(buffer, font, &text, x, y, width, &style);
+        }
+    }
+}
+
+/// Everything the band layer of a frame depends on besides the pattern
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BandLayerKey {
+    window: Rect,
+    monitor_size: (u32, u32),
+    boundary_size: i32,
+    boundary_mode: BoundaryMode,
+    brightness: f32,
+    background: u32,
+    boundary_color: u32,
+    corner_radius: u32,
+    dither: bool,
+}
+
+impl BandLayerKey {
+    /// The same layer with the window at `(x, y)`
+    fn moved_to(self, x: i32, y: i32) -> Self {
+        Self { window: Rect { x, y, ..self.window }, ..self }
+    }
+}
+
+/// Renders frames like `render_frame`, but keeps the bands in a layer of its own and,
+/// when only the window position changed, repaints just the previous and current band
+/// rects instead of every pixel. Overlays are drawn on a copy of the layer each frame.
+///
+/// `band_pattern` is assumed not to change between frames. Reduced-resolution rendering,
+/// concentric frames, the heatmap, letterboxing and `BoundaryMode::EdgeOnly`, whose lit
+/// pixels aren't the band rects, fall back to full redraws.
+#[derive(Debug, Default)]
+pub struct IncrementalRenderer {
+    bands: Vec<u32>,
+    previous: Option<BandLayerKey>,
+}
+
+impl IncrementalRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Renders one frame into `buffer`; same arguments as `render_frame`
+    pub fn render(
+        &mut self,
+        buffer: &mut [u32],
+        width: u32,
+        height: u32,
+        window_pos: (i32, i32),
+        monitor_size: (u32, u32),
+        options: &RenderOptions,
+    ) {
+        let full_redraw = options.render_scale > 1
+            || !options.frames.is_empty()
+            || options.heatmap
+            || options.target_aspect.is_some()
+            || options.boundary_mode == BoundaryMode::EdgeOnly;
+        if full_redraw {
+            self.previous = None;
+            render_frame(buffer, width, height, window_pos, monitor_size, options);
+            return;
+        }
+        let window = Rect::new(window_pos.0, window_pos.1, width as i32, height as i32);
+        let key = BandLayerKey {
+            window,
+            monitor_size,
+            boundary_size: options.boundary_size,
+            boundary_mode: options.boundary_mode,
+            brightness: options.brightness,
+            background: options.background,
+            boundary_color: options.boundary_color,
+            corner_radius: options.corner_radius,
+            dither: options.dither,
+        };
+        match self.previous {
+            Some(previous) if previous == key => {}
+            Some(previous) if previous.moved_to(window.x, window.y) == key => {
+                // Only moved: clear where the bands were, then paint where they are now
+                for (_, band) in visible_bands(previous.window, monitor_size, options.boundary_size) {
+                    let area = band.relative_to((previous.window.x, previous.window.y));
+                    for y in area.y..area.bottom() {
+                        let row = (y as u32 * width) as usize;
+                        self.bands[row + area.x as usize..row + area.right() as usize].fill(options.background);
+                    }
+                }
+                for (_, band) in visible_bands(window, monitor_size, options.boundary_size) {
+                    paint_bands(&mut self.bands, width, window, band.relative_to((window.x, window.y)), monitor_size, options);
+                }
+            }
+            _ => {
+                self.bands.clear();
+                self.bands.resize((width * height) as usize, options.background);
+                paint_bands(&mut self.bands, width, window, Rect::new(0, 0, width as i32, height as i32), monitor_size, options);
+            }
+        }
+        self.previous = Some(key);
+        buffer.copy_from_slice(&self.bands);
+        draw_overlays(buffer, width, height, window, monitor_size, options);
+    }
+}
+
+/// The part of a window content is drawn into, in window coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    pub rect: Rect,
+}
+
+impl Viewport {
+    /// The largest centred `aspect` (width / height) area of a `width` x `height` window,
+    /// with bars left and right or above and below
+    pub fn letterbox(width: u32, height: u32, aspect: f32) -> Self {
+        let (w, h) = (width as f32, height as f32);
+        let (inner_width, inner_height) = if w / h > aspect {
+            (((h * aspect).round() as u32).min(width), height)
+        } else {
+            (width, ((w / aspect).round() as u32).min(height))
+        };
+        let x = (width - inner_width) / 2;
+        let y = (height - inner_height) / 2;
+        Self { rect: Rect::new(x as i32, y as i32, inner_width as i32, inner_height as i32) }
+    }
+
+    /// Where the viewport sits in the world for a window at `window_pos`, so content stays
+    /// put rather than following the bars
+    pub const fn world_position(&self, window_pos: (i32, i32)) -> (i32, i32) {
+        (window_pos.0 + self.rect.x, window_pos.1 + self.rect.y)
+    }
+
+    /// Copies `pixels`, a frame the size of the viewport, into place in `buffer`
+    pub fn blit(&self, buffer: &mut [u32], width: u32, pixels: &[u32]) {
+        if self.rect.is_empty() {
+            return;
+        }
+        for (row, src) in pixels.chunks_exact(self.rect.width as usize).enumerate() {
+            let start = ((self.rect.y as usize + row) * width as usize) + self.rect.x as usize;
+            buffer[start..start + src.len()].copy_from_slice(src);
+        }
+    }
+}
+
+/// Parses an aspect ratio as `W:H` (e.g. `16:9`) or a plain ratio (e.g. `1.78`)
+pub fn parse_aspect(s: &str) -> Option<f32> {
+    let aspect = match s.split_once(':') {
+        Some((width, height)) => width.trim().parse::<f32>().ok()? / height.trim().parse::<f32>().ok()?,
+        None => s.trim().parse().ok()?,
+    };
+    (aspect.is_finite() && aspect > 0.0).then_some(aspect)
+}
+
+/// Fills `buffer` with the letterbox color and renders the frame into `viewport` only
+fn render_letterboxed(
+    buffer: &mut [u32],
+    width: u32,
+    viewport: Viewport,
+    window_pos: (i32, i32),
+    monitor_size: (u32, u32),
+    options: &RenderOptions,
+) {
+    buffer.fill(options.letterbox_color);
+    if viewport.rect.is_empty() {
+        return;
+    }
+    let inner = RenderOptions { target_aspect: None, ..options.clone() };
+    let (inner_width, inner_height) = (viewport.rect.width as u32, viewport.rect.height as u32);
+    let mut pixels = vec![options.background; (inner_width * inner_height) as usize];
+    render_frame(&mut pixels, inner_width, inner_height, viewport.world_position(window_pos), monitor_size, &inner);
+    viewport.blit(buffer, width, &pixels);
+}
+
+/// Coarsest render scale `fit_render_scale` falls back to before giving up
+pub const MAX_RENDER_SCALE: u32 = 16;
+
+/// The render scale to draw a `width` x `height` frame at so that at most `max_pixels`
+/// are rendered: `render_scale` itself if that fits, else the smallest coarser scale
+/// that does, up to `MAX_RENDER_SCALE`. `None` means no scale fits and the frame
+/// shouldn't be drawn at all.
+pub fn fit_render_scale(width: u32, height: u32, render_scale: u32, max_pixels: u64) -> Option<u32> {
+    let rendered = |scale: u32| width.div_ceil(scale) as u64 * height.div_ceil(scale) as u64;
+    (render_scale.max(1)..=MAX_RENDER_SCALE.max(render_scale)).find(|&scale| rendered(scale) <= max_pixels)
+}
+
+/// Renders into a buffer `render_scale` times smaller in each direction, with every
+/// coordinate and size divided to match, then upscales it into `buffer`
+fn render_frame_reduced(
+    buffer: &mut [u32],
+    width: u32,
+    height: u32,
+    window_pos: (i32, i32),
+    monitor_size: (u32, u32),
+    options: &RenderOptions,
+) {
+    let factor = options.render_scale;
+    let f = factor as i32;
+    let low_width = width.div_ceil(factor);
+    let low_height = height.div_ceil(factor);
+    let reduced = RenderOptions {
+        boundary_size: options.boundary_size / f,
+        frames: options.frames.iter().map(|&(threshold, color)| (threshold / f, color)).collect(),
+        corner_radius: options.corner_radius / factor,
+        text: TextStyle {
+            scale: (options.text.scale / f).max(1),
+            shadow: options.text.shadow.map(|(dx, dy, color)| (dx / f, dy / f, color)),
+            ..options.text
+        },
+        labels: options
+            .labels
+            .iter()
+            .map(|label| Label {
+                x: label.x.div_euclid(f),
+                y: label.y.div_euclid(f),
+                style: TextStyle {
+                    scale: (label.style.scale / f).max(1),
+                    line_spacing: label.style.line_spacing / f,
+                    shadow: label.style.shadow.map(|(dx, dy, color)| (dx / f, dy / f, color)),
+                    ..label.style
+                },
+                ..label.clone()
+            })
+            .collect(),
+        render_scale: 1,
+        ..options.clone()
+    };
+    let mut low = vec![options.background; (low_width * low_height) as usize];
+    render_frame(
+        &mut low,
+        low_width,
+        low_height,
+        (window_pos.0.div_euclid(f), window_pos.1.div_euclid(f)),
+        (monitor_size.0 / factor, monitor_size.1 / factor),
+        &reduced,
+    );
+    upscale_nearest(&low, low_width, buffer, width, height, factor);
+}
+
+/// Expands `src` (`src_width` wide) by `factor` in both directions into `dst`, cropping
+/// whatever falls outside `dst_width` x `dst_height`
+pub fn upscale_nearest(src: &[u32], src_width: u32, dst: &mut [u32], dst_width: u32, dst_height: u32, factor: u32) {
+    for y in 0..dst_height {
+        let src_row = ((y / factor) * src_width) as usize;
+        let dst_row = (y * dst_width) as usize;
+        for x in 0..dst_width {
+            dst[dst_row + x as usize] = src[src_row + (x / factor) as usize];
+        }
+    }
+}
+
+/// Clockwise turn applied to every frame just before it's presented, for displays
+/// mounted sideways or upside down
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Rotation {
+    #[default]
+    None,
+    Cw90,
+    Cw180,
+    Cw270,
+}
+
+impl Rotation {
+    /// Parses a clockwise angle in degrees: 0, 90, 180 or 270
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim() {
+            "0" => Some(Rotation::None),
+            "90" => Some(Rotation::Cw90),
+            "180" => Some(Rotation::Cw180),
+            "270" => Some(Rotation::Cw270),
+            _ => None,
+        }
+    }
+
+    /// Whether the turned frame has its width and height swapped
+    pub const fn is_quarter_turn(self) -> bool {
+        matches!(self, Rotation::Cw90 | Rotation::Cw270)
+    }
+
+    /// Size of a `width` x `height` frame once turned
+    pub const fn rotated_size(self, width: u32, height: u32) -> (u32, u32) {
+        if self.is_quarter_turn() { (height, width) } else { (width, height) }
+    }
+
+    /// Where pixel `(x, y)` of a `width` x `height` frame ends up once rotated
+    pub const fn map(self, x: u32, y: u32, width: u32, height: u32) -> (u32, u32) {
+        match self {
+            Rotation::None => (x, y),
+            Rotation::Cw90 => (height - 1 - y, x),
+            Rotation::Cw180 => (width - 1 - x, height - 1 - y),
+            Rotation::Cw270 => (y, width - 1 - x),
+        }
+    }
+}
+
+/// Copies `src`, a `width` x `height` frame, into `dst` turned by `rotation`; `dst` is
+/// `rotation.rotated_size`, i.e. `height` wide after a quarter turn
+pub fn rotate_frame(src: &[u32], width: u32, height: u32, dst: &mut [u32], rotation: Rotation) {
+    let (dst_width, _) = rotation.rotated_size(width, height);
+    for (y, row) in src.chunks_exact(width as usize).enumerate() {
+        for (x, &pixel) in row.iter().enumerate() {
+            let (dx, dy) = rotation.map(x as u32, y as u32, width, height);
+            dst[(dy * dst_width + dx) as usize] = pixel;
+        }
+    }
+}
+
+/// Opaque color for linear buffer index `index` in the `--debug-index` view: a hash of
+/// `index % modulus`, so pixels `modulus` apart match and neighbours rarely do
+pub const fn debug_index_color(index: usize, modulus: u32) -> u32 {
+    let mut hash = (index as u64 % if modulus == 0 { 1 } else { modulus as u64 }).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    0xFF000000 | (hash >> 40) as u32
+}
+
+/// Colors every pixel by its own index with `debug_index_color`, showing how the
+/// buffer's rows line up: a stride bug shears the pattern
+pub fn fill_debug_index(buffer: &mut [u32], modulus: u32) {
+    for (index, pixel) in buffer.iter_mut().enumerate() {
+        *pixel = debug_index_color(index, modulus);
+    }
+}
+
+/// Rotates a row-major buffer by 180° in place, so pixel `(x, y)` moves to
+/// `(width - 1 - x, height - 1 - y)`, e.g. for a physically inverted display
+pub fn rotate_180(buffer: &mut [u32]) {
+    buffer.reverse();
+}
+
+/// Mirrors a row-major buffer `width` pixels wide left to right in place, so pixel
+/// `(x, y)` moves to `(width - 1 - x, y)`, e.g. for a teleprompter mirror
+pub fn mirror_x(buffer: &mut [u32], width: u32) {
+    for row in buffer.chunks_exact_mut(width as usize) {
+        row.reverse();
+    }
+}
+
+/// Software cursor arrow, hotspot at the top-left: `#` is drawn in the fill color, `.` in
+/// the outline color and spaces are left alone
+pub const CURSOR_ARROW: [&str; 12] = [
+    ".",
+    "..",
+    ".#.",
+    ".##.",
+    ".###.",
+    ".####.",
+    ".#####.",
+    ".######.",
+    ".####....",
+    ".#..#.",
+    "..  .#.",
+    "     ..",
+];
+pub const CURSOR_FILL: u32 = 0xFFFFFFFF;
+pub const CURSOR_OUTLINE: u32 = 0xFF000000;
+
+/// Draws `CURSOR_ARROW` with its hotspot at `(x, y)`, skipping pixels outside the buffer
+pub fn draw_cursor_glyph(buffer: &mut [u32], width: u32, height: u32, x: i32, y: i32) {
+    for (row, line) in CURSOR_ARROW.iter().enumerate() {
+        for (col, cell) in line.bytes().enumerate() {
+            let color = match cell {
+                b'#' => CURSOR_FILL,
+                b'.' => CURSOR_OUTLINE,
+                _ => continue,
+            };
+            let (px, py) = (x as i64 + col as i64, y as i64 + row as i64);
+            if (0..width as i64).contains(&px) && (0..height as i64).contains(&py) {
+                buffer[(py as u32 * width + px as u32) as usize] = color;
+            }
+        }
+    }
+}
+
+/// Draws a 1px line from `from` to `to` inclusive (Bresenham), skipping pixels outside
+/// the buffer
+pub fn draw_line(buffer: &mut [u32], width: u32, height: u32, from: (i32, i32), to: (i32, i32), color: u32) {
+    let (mut x, mut y) = from;
+    let dx = (to.0 - x).abs();
+    let dy = -(to.1 - y).abs();
+    let step_x = if x < to.0 { 1 } else { -1 };
+    let step_y = if y < to.1 { 1 } else { -1 };
+    let mut err = dx + dy;
+    loop {
+        if x >= 0 && y >= 0 && x < width as i32 && y < height as i32 {
+            buffer[(y as u32 * width + x as u32) as usize] = color;
+        }
+        if (x, y) == to {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += step_x;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += step_y;
+        }
+    }
+}
+
+/// Outlines the window interior with a 1px rectangle along its outermost pixels
+pub fn draw_window_border(buffer: &mut [u32], width: u32, height: u32, color: u32) {
+    if width == 0 || height == 0 {
+        return;
+    }
+    let (right, bottom) = (width as i32 - 1, height as i32 - 1);
+    draw_line(buffer, width, height, (0, 0), (right, 0), color);
+    draw_line(buffer, width, height, (0, bottom), (right, bottom), color);
+    draw_line(buffer, width, height, (0, 0), (0, bottom), color);
+    draw_line(buffer, width, height, (right, 0), (right, bottom), color);
+}
+
+/// Fills a circle centred on `center`, blending `color` over the existing pixels with
+/// `alpha` (255 = opaque). Pixels outside the buffer are skipped.
+pub fn fill_circle(buffer: &mut [u32], width: u32, height: u32, center: (i32, i32), radius: i32, color: u32, alpha: u8) {
+    let (cx, cy) = center;
+    let min_y = (cy - radius).max(0);
+    let max_y = (cy + radius).min(height as i32 - 1);
+    let min_x = (cx - radius).max(0);
+    let max_x = (cx + radius).min(width as i32 - 1);
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            let (dx, dy) = (x - cx, y - cy);
+            if dx * dx + dy * dy <= radius * radius {
+                let idx = (y as u32 * width + x as u32) as usize;
+                buffer[idx] = blend_pixel(buffer[idx], color, alpha, false);
+            }
+        }
+    }
+}
+
+/// Fills `rect` (buffer coordinates) with `color`, clipped to the buffer
+pub fn fill_rect(buffer: &mut [u32], width: u32, height: u32, rect: Rect, color: u32) {
+    let Some(visible) = rect.intersect(&Rect::new(0, 0, width as i32, height as i32)) else {
+        return;
+    };
+    for y in visible.y..visible.bottom() {
+        let row = (y as u32 * width) as usize;
+        buffer[row + visible.x as usize..row + visible.right() as usize].fill(color);
+    }
+}
+
+/// Blends `color` over every pixel of `rect` (buffer coordinates) with `alpha`
+/// (255 = opaque), clipped to the buffer, e.g. for a panel behind text
+pub fn shade_rect(buffer: &mut [u32], width: u32, height: u32, rect: Rect, color: u32, alpha: u8) {
+    let Some(visible) = rect.intersect(&Rect::new(0, 0, width as i32, height as i32)) else {
+        return;
+    };
+    for y in visible.y..visible.bottom() {
+        let row = (y as u32 * width) as usize;
+        for pixel in &mut buffer[row + visible.x as usize..row + visible.right() as usize] {
+            *pixel = blend_pixel(*pixel, color, alpha, false);
+        }
+    }
+}
+
+/// Draws a horizontal progress bar: the left `fraction` (clamped to 0..=1) of `rect` in
+/// `fg` and the rest in `bg`, clipped to the buffer
+pub fn draw_progress(buffer: &mut [u32], rect: Rect, fraction: f32, fg: u32, bg: u32, width: u32, height: u32) {
+    let filled = (rect.width as f32 * fraction.clamp(0.0, 1.0)).round() as i32;
+    fill_rect(buffer, width, height, Rect { width: filled, ..rect }, fg);
+    fill_rect(buffer, width, height, Rect::new(rect.x + filled, rect.y, rect.width - filled, rect.height), bg);
+}
+
+/// Outline color of the capture safe area
+pub const SAFE_AREA_COLOR: u32 = 0xFFFF00FF; // Magenta
+
+/// The part of a `width` x `height` window inside an inset of `percent` (clamped to
+/// 0..=50) of its size on each side, in window coordinates
+pub fn safe_area_rect(width: u32, height: u32, percent: f32) -> Rect {
+    let fraction = percent.clamp(0.0, 50.0) / 100.0;
+    let inset_x = (width as f32 * fraction).round() as i32;
+    let inset_y = (height as f32 * fraction).round() as i32;
+    Rect::new(inset_x, inset_y, width as i32 - 2 * inset_x, height as i32 - 2 * inset_y)
+}
+
+/// Draws a 1px outline just inside the edge of `safe_area_rect`
+pub fn draw_safe_area(buffer: &mut [u32], width: u32, height: u32, percent: f32, color: u32) {
+    let area = safe_area_rect(width, height, percent);
+    if area.is_empty() {
+        return;
+    }
+    let (left, top, right, bottom) = (area.x, area.y, area.right() - 1, area.bottom() - 1);
+    draw_line(buffer, width, height, (left, top), (right, top), color);
+    draw_line(buffer, width, height, (right, top), (right, bottom), color);
+    draw_line(buffer, width, height, (right, bottom), (left, bottom), color);
+    draw_line(buffer, width, height, (left, bottom), (left, top), color);
+}
+
+/// Radius of the compass dial in pixels
+const COMPASS_RADIUS: i32 = 24;
+
+/// Draws a dial in the top-right corner with an arrow along `direction` (a unit vector,
+/// y down), or just a centre dot when there's no direction
+pub fn draw_compass(buffer: &mut [u32], width: u32, height: u32, direction: Option<(f32, f32)>, color: u32) {
+    let center = (width as i32 - COMPASS_RADIUS - 8, COMPASS_RADIUS + 8);
+    // Darken what's underneath so the arrow reads over the bands
+    fill_circle(buffer, width, height, center, COMPASS_RADIUS, BACKGROUND, 160);
+    let Some((dx, dy)) = direction else {
+        fill_circle(buffer, width, height, center, 3, color, 255);
+        return;
+    };
+    let point = |length: f32, (ux, uy): (f32, f32)| {
+        (center.0 + (ux * length).round() as i32, center.1 + (uy * length).round() as i32)
+    };
+    let reach = (COMPASS_RADIUS - 4) as f32;
+    let tip = point(reach, (dx, dy));
+    draw_line(buffer, width, height, point(reach / 2.0, (-dx, -dy)), tip, color);
+    // Arrowhead barbs at +-150 degrees from the arrow's direction
+    let (sin, cos) = 150f32.to_radians().sin_cos();
+    for sin in [sin, -sin] {
+        let barb = (dx * cos - dy * sin, dx * sin + dy * cos);
+        let end = (tip.0 + (barb.0 * 8.0).round() as i32, tip.1 + (barb.1 * 8.0).round() as i32);
+        draw_line(buffer, width, height, tip, end, color);
+    }
+}
+
+/// Color of the monitor edge reference lines
+pub const EDGE_LINE_COLOR: u32 = 0xFFFF00FF; // Magenta
+
+/// Window columns and rows of the outermost monitor pixels (world x/y of 0 and
+/// width/height - 1) that fall inside a `width` x `height` window at `window_pos`
+pub fn monitor_edge_lines(width: u32, height: u32, window_pos: (i32, i32), monitor_size: (u32, u32)) -> (Vec<i32>, Vec<i32>) {
+    let inside = |world: i64, origin: i32, extent: u32| {
+        let local = world - origin as i64;
+        (0..extent as i64).contains(&local).then_some(local as i32)
+    };
+    let last = |size: u32| size.saturating_sub(1) as i64;
+    let columns = [0, last(monitor_size.0)].into_iter().filter_map(|x| inside(x, window_pos.0, width)).collect();
+    let rows = [0, last(monitor_size.1)].into_iter().filter_map(|y| inside(y, window_pos.1, height)).collect();
+    (columns, rows)
+}
+
+/// Draws 1px lines along the outermost pixels of the monitor wherever the window shows
+/// them, marking exactly where the physical screen ends
+pub fn draw_monitor_edges(buffer: &mut [u32], width: u32, height: u32, window_pos: (i32, i32), monitor_size: (u32, u32), color: u32) {
+    let (columns, rows) = monitor_edge_lines(width, height, window_pos, monitor_size);
+    for x in columns {
+        draw_line(buffer, width, height, (x, 0), (x, height as i32 - 1), color);
+    }
+    for y in rows {
+        draw_line(buffer, width, height, (0, y), (width as i32 - 1, y), color);
+    }
+}
+
+/// Draws a dot at each past window position (oldest first), fading older ones out.
+/// Positions are world coordinates of the window's top-left corner.
+pub fn draw_trail(buffer: &mut [u32], width: u32, height: u32, window_pos: (i32, i32), trail: impl ExactSizeIterator<Item = (i32, i32)>) {
+    const DOT_RADIUS: i32 = 3;
+    const TRAIL_COLOR: u32 = 0xFFFF8000; // Orange
+    let len = trail.len();
+    for (age_rank, (x, y)) in trail.enumerate() {
+        // The newest entry is fully opaque, the oldest almost invisible
+        let alpha = (255 * (age_rank + 1) / len) as u8;
+        let center = world_to_window((x, y), window_pos);
+        fill_circle(buffer, width, height, center, DOT_RADIUS, TRAIL_COLOR, alpha);
+    }
+}
+
+/// Lays out every glyph in `LETTER_DATA` order, each with a small label underneath
+pub fn draw_font_grid(buffer: &mut [u32], width: u32, height: u32) {
+    const PADDING: i32 = FONT_GRID_PADDING;
+    const LABEL_HEIGHT: i32 = HEIGHT + PADDING;
+    let chars = font_grid_chars();
+    let (scale, columns) = font_grid_layout(chars.len(), width, height);
+    let cell_width = font_grid_cell_width(scale);
+    let cell_height = HEIGHT * scale + LABEL_HEIGHT + PADDING;
+
+    for (i, &c) in chars.iter().enumerate() {
+        let x = PADDING + (i % columns) as i32 * cell_width;
+        let y = PADDING + (i / columns) as i32 * cell_height;
+        draw_char(buffer, x, y, &BUILTIN_FONT, c, width, &TextStyle { scale, ..TextStyle::default() });
+        // Labels are 1x whatever the glyph scale, which is what the layout leaves room for
+        let label = font_grid_label(c);
+        draw_str(buffer, &BUILTIN_FONT, &label, x, y + HEIGHT * scale + PADDING / 2, width, &TextStyle { scale: 1, ..TextStyle::default() });
+    }
+}
+
+/// Every character in the built-in font, in grid order: `LETTER_DATA` then `LATIN1_GLYPHS`
+pub fn font_grid_chars() -> Vec<char> {
+    LETTER_DATA.iter().map(|&byte| char::from(byte)).chain(LATIN1_GLYPHS.iter().map(|(c, _)| *c)).collect()
+}
+
+/// The label under a font grid cell: the character itself
+pub fn font_grid_label(c: char) -> String {
+    c.to_string()
+}
+
+const FONT_GRID_PADDING: i32 = 4;
+/// Width of a one-character label
+const FONT_GRID_LABEL_WIDTH: i32 = ADVANCE;
+
+/// A cell is wide enough for both the glyph at `scale` and its label
+const fn font_grid_cell_width(scale: i32) -> i32 {
+    let glyph = ADVANCE * scale;
+    (if glyph > FONT_GRID_LABEL_WIDTH { glyph } else { FONT_GRID_LABEL_WIDTH }) + FONT_GRID_PADDING
+}
+
+/// Picks the largest glyph scale whose grid of `count` cells fits in the window,
+/// returning the scale and the number of columns used
+pub fn font_grid_layout(count: usize, width: u32, height: u32) -> (i32, usize) {
+    const PADDING: i32 = FONT_GRID_PADDING;
+    let mut scale = 16;
+    while scale > 1 {
+        let cell_width = font_grid_cell_width(scale);
+        let cell_height = HEIGHT * scale + HEIGHT + 2 * PADDING;
+        let columns = ((width as i32 - PADDING) / cell_width).max(1) as usize;
+        let rows = count.div_ceil(columns) as i32;
+        if rows * cell_height + PADDING <= height as i32 {
+            return (scale, columns);
+        }
+        scale -= 1;
+    }
+    let columns = ((width as i32 - PADDING) / font_grid_cell_width(1)).max(1) as usize;
+    (1, columns)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pixels of `color` inside `area` of a `width`-wide buffer
+    fn count_in(buffer: &[u32], width: u32, area: Rect, color: u32) -> usize {
+        (area.y..area.bottom())
+            .flat_map(|y| (area.x..area.right()).map(move |x| (y * width as i32 + x) as usize))
+            .filter(|&i| buffer[i] == color)
+            .count()
+    }
+
+    /// A `width` x `height` window at `window_pos` on a 1920x1080 monitor
+    fn render(width: u32, height: u32, window_pos: (i32, i32), options: &RenderOptions) -> Vec<u32> {
+        let mut buffer = vec![0; (width * height) as usize];
+        render_frame(&mut buffer, width, height, window_pos, (1920, 1080), options);
+        buffer
+    }
+
+    #[test]
+    fn fractional_scale_factors_round_to_whole_glyph_scales() {
+        for (factor, scale) in [(1.0, 3), (1.25, 4), (1.5, 5), (2.0, 6)] {
+            assert_eq!(text_scale_for(factor), scale, "scale factor {factor}");
+        }
+    }
+
+    #[test]
+    fn raw_pixels_ignores_the_scale_factor() {
+        for factor in [0.5, 1.0, 1.5, 2.0, 3.0] {
+            assert_eq!(effective_text_scale(factor, true), BASE_TEXT_SCALE, "scale factor {factor}");
+            assert_eq!(effective_text_scale(factor, false), text_scale_for(factor), "scale factor {factor}");
+        }
+        assert_ne!(effective_text_scale(2.0, false), effective_text_scale(2.0, true));
+    }
+
+    #[test]
+    fn heatmap_is_cool_in_the_centre_and_hot_at_the_edge() {
+        const COOL: u32 = HEATMAP_STOPS[0].1;
+        const HOT: u32 = HEATMAP_STOPS[HEATMAP_STOPS.len() - 1].1;
+        assert_eq!(heatmap_color(540, (1920, 1080)), COOL);
+        assert_eq!(heatmap_color(0, (1920, 1080)), HOT);
+        assert_eq!(heatmap_color(-50, (1920, 1080)), HOT);
+
+        // A window over the middle of the left edge, whose right side reaches the centre
+        let options = RenderOptions { heatmap: true, ..RenderOptions::default() };
+        let buffer = render(960, 20, (0, 530), &options);
+        let (edge, centre) = (buffer[10 * 960], buffer[10 * 960 + 959]);
+        assert_eq!(edge, HOT);
+        let red = |color: u32| (color >> 16) & 0xFF;
+        let blue = |color: u32| color & 0xFF;
+        assert!(blue(centre) > red(centre), "{centre:08X} should be cool");
+        assert!(red(edge) > blue(edge), "{edge:08X} should be hot");
+    }
+
+    #[test]
+    fn switching_baked_text_changes_the_rendered_glyphs() {
+        let defaults = RenderOptions::default();
+        let rect = hidden_text_rect((1920, 1080), defaults.hidden_text().source.len(), defaults.text.scale);
+        let frame = |baked_text| {
+            let options = RenderOptions { baked_text, ..defaults.clone() };
+            render(rect.width as u32, rect.height as u32, (rect.x, rect.y), &options)
+        };
+        let (flag, decoy) = (frame(0), frame(1));
+        assert!(flag.contains(&TEXT_COLOR) && decoy.contains(&TEXT_COLOR));
+        assert!(flag != decoy);
+        // Each index shows its own string, and an out-of-range one falls back to the flag
+        assert_eq!(RenderOptions { baked_text: 2, ..defaults.clone() }.hidden_text(), &BAKED_TEXTS[2]);
+        assert!(frame(BAKED_TEXTS.len()) == flag);
+    }
+
+    #[test]
+    fn hidden_text_rect_measures_the_selected_text() {
+        let scale = RenderOptions::default().text.scale;
+        let flag = hidden_text_rect((1920, 1080), BAKED_TEXTS[0].source.len(), scale);
+        let decoy = hidden_text_rect((1920, 1080), BAKED_TEXTS[1].source.len(), scale);
+        assert_eq!((decoy.x, decoy.y, decoy.height), (flag.x, flag.y, flag.height));
+        assert_eq!(decoy.width, measure_text(BAKED_TEXTS[1].source.len(), scale).0);
+        assert!(decoy.width < flag.width);
+    }
+
+    #[test]
+    fn hidden_text_fit_flags_windows_too_small_to_show_it_whole() {
+        // 10 glyphs at scale 2 need 10 * 6 * 2 - 2 by 8 * 2 pixels
+        let needed = (118, 16);
+        assert_eq!(measure_text(10, 2), needed);
+        assert_eq!(hidden_text_fit((118, 16), (1920, 1080), 10, 2), TextFit::Fits);
+        // One pixel short in either direction is too small, but a bigger window would do
+        assert_eq!(hidden_text_fit((117, 400), (1920, 1080), 10, 2), TextFit::TooSmall { needed });
+        assert_eq!(hidden_text_fit((400, 15), (1920, 1080), 10, 2), TextFit::TooSmall { needed });
+        // No window fits if the monitor itself is too small
+        assert_eq!(hidden_text_fit((100, 100), (100, 100), 10, 2), TextFit::Never { needed });
+    }
+
+    #[test]
+    fn rotations_move_a_known_pixel_clockwise() {
+        // Pixel (1, 0) of a 4x3 frame, one in from the top-left corner along the top row
+        let cases = [
+            (Rotation::None, (1, 0), (4, 3)),
+            (Rotation::Cw90, (2, 1), (3, 4)),
+            (Rotation::Cw180, (2, 2), (4, 3)),
+            (Rotation::Cw270, (0, 2), (3, 4)),
+        ];
+        for (rotation, (x, y), size) in cases {
+            assert_eq!(rotation.map(1, 0, 4, 3), (x, y), "{rotation:?}");
+            assert_eq!(rotation.rotated_size(4, 3), size, "{rotation:?}");
+            let mut frame = vec![0; 4 * 3];
+            frame[1] = 7;
+            let mut rotated = vec![0; 4 * 3];
+            rotate_frame(&frame, 4, 3, &mut rotated, rotation);
+            assert_eq!(rotated.iter().position(|&pixel| pixel == 7), Some((y * size.0 + x) as usize), "{rotation:?}");
+        }
+        // A quarter turn each way gets back to the start
+        let (x, y) = Rotation::Cw90.map(1, 0, 4, 3);
+        assert_eq!(Rotation::Cw270.map(x, y, 3, 4), (1, 0));
+    }
+
+    #[test]
+    fn debug_index_colors_repeat_every_modulus_pixels() {
+        let mut buffer = vec![0; 64 * 3];
+        fill_debug_index(&mut buffer, 7);
+        for index in 0..buffer.len() - 7 {
+            assert_eq!(buffer[index], buffer[index + 7], "index {index}");
+            assert_eq!(buffer[index], debug_index_color(index, 7));
+        }
+        // Within one period every index gets its own opaque color
+        let period: std::collections::HashSet<_> = buffer[..7].iter().collect();
+        assert_eq!(period.len(), 7);
+        assert!(buffer.iter().all(|&pixel| pixel >> 24 == 0xFF));
+        // A zero modulus is one color rather than a division by zero
+        assert_eq!(debug_index_color(5, 0), debug_index_color(0, 0));
+    }
+
+    #[test]
+    fn rounded_corner_carves_background_just_inside_the_inner_corner() {
+        let square = RenderOptions { boundary_size: 100, ..RenderOptions::default() };
+        let rounded = RenderOptions { corner_radius: 20, ..square.clone() };
+        // The window covers the top-left corner, whose bands meet at world (100, 100)
+        let (square, rounded) = (render(200, 200, (0, 0), &square), render(200, 200, (0, 0), &rounded));
+        let at = |buffer: &[u32], x: usize, y: usize| buffer[y * 200 + x];
+        assert_eq!(at(&square, 99, 99), BOUNDARY_COLOR);
+        // Right in the corner, and up the edge of the vertical band to the end of the arc
+        assert_eq!(at(&rounded, 99, 99), BACKGROUND);
+        assert_eq!(at(&rounded, 99, 80), BACKGROUND);
+        assert_eq!(at(&rounded, 99, 79), BOUNDARY_COLOR);
+        // Diagonally just outside the arc, and the rest of the bands
+        assert_eq!(at(&rounded, 85, 85), BOUNDARY_COLOR);
+        assert_eq!(at(&rounded, 5, 5), BOUNDARY_COLOR);
+        assert_eq!(at(&rounded, 150, 50), BOUNDARY_COLOR);
+    }
+
+    #[test]
+    fn rotating_180_moves_a_pixel_to_the_opposite_corner() {
+        // Pixel (1, 0) of a 4x3 buffer
+        let mut buffer = vec![0; 4 * 3];
+        buffer[1] = 7;
+        rotate_180(&mut buffer);
+        // Lands on (4 - 1 - 1, 3 - 1 - 0) = (2, 2)
+        assert_eq!(buffer.iter().position(|&pixel| pixel == 7), Some(2 * 4 + 2));
+    }
+
+    #[test]
+    fn mirroring_moves_a_pixel_to_the_opposite_column() {
+        // Pixel (1, 2) of a 5x3 buffer
+        let mut buffer = vec![0; 5 * 3];
+        buffer[2 * 5 + 1] = 7;
+        mirror_x(&mut buffer, 5);
+        // Lands on (5 - 1 - 1, 2) = (3, 2), same row
+        assert_eq!(buffer.iter().position(|&pixel| pixel == 7), Some(2 * 5 + 3));
+        mirror_x(&mut buffer, 5);
+        assert_eq!(buffer.iter().position(|&pixel| pixel == 7), Some(2 * 5 + 1));
+    }
+
+    #[test]
+    fn cursor_glyph_is_blitted_at_the_hotspot() {
+        const BACKGROUND: u32 = 0xFF123456;
+        let mut buffer = vec![BACKGROUND; 30 * 30];
+        draw_cursor_glyph(&mut buffer, 30, 30, 10, 5);
+        for (i, &pixel) in buffer.iter().enumerate() {
+            let (x, y) = (i % 30, i / 30);
+            let cell = (y.checked_sub(5), x.checked_sub(10));
+            let expected = match cell {
+                (Some(row), Some(col)) => match CURSOR_ARROW.get(row).and_then(|line| line.as_bytes().get(col)) {
+                    Some(b'#') => CURSOR_FILL,
+                    Some(b'.') => CURSOR_OUTLINE,
+                    _ => BACKGROUND,
+                },
+                _ => BACKGROUND,
+            };
+            assert_eq!(pixel, expected, "pixel ({x}, {y})");
+        }
+        assert_eq!(buffer[5 * 30 + 10], CURSOR_OUTLINE);
+
+        // Partly off every side: only the visible part is drawn
+        let mut buffer = vec![BACKGROUND; 4 * 4];
+        draw_cursor_glyph(&mut buffer, 4, 4, -2, -2);
+        draw_cursor_glyph(&mut buffer, 4, 4, 3, 3);
+        assert_eq!(buffer[3 * 4 + 3], CURSOR_OUTLINE);
+    }
+
+    #[test]
+    fn window_border_sets_the_edges_and_leaves_the_interior() {
+        const BORDER: u32 = 0xFFFF0000;
+        let (width, height) = (7, 5);
+        let mut buffer = vec![0; width * height];
+        draw_window_border(&mut buffer, width as u32, height as u32, BORDER);
+        for (i, &pixel) in buffer.iter().enumerate() {
+            let (x, y) = (i % width, i / width);
+            let on_edge = x == 0 || y == 0 || x == width - 1 || y == height - 1;
+            assert_eq!(pixel == BORDER, on_edge, "pixel ({x}, {y})");
+        }
+        // A window one pixel high is all border
+        let mut line = vec![0; 4];
+        draw_window_border(&mut line, 4, 1, BORDER);
+        assert_eq!(line, [BORDER; 4]);
+        draw_window_border(&mut [], 0, 0, BORDER);
+    }
+
+    #[test]
+    fn letterbox_confines_content_to_the_inner_rect() {
+        let aspect = parse_aspect("16:9").unwrap();
+        // Too wide: bars left and right. Too tall: bars above and below.
+        assert_eq!(Viewport::letterbox(400, 100, aspect).rect, Rect::new(111, 0, 178, 100));
+        assert_eq!(Viewport::letterbox(160, 300, aspect).rect, Rect::new(0, 105, 160, 90));
+        assert_eq!(Viewport::letterbox(160, 90, aspect).rect, Rect::new(0, 0, 160, 90));
+
+        // In the top-left corner of the monitor every content pixel is band
+        let options = RenderOptions { target_aspect: Some(aspect), ..RenderOptions::default() };
+        let buffer = render(400, 100, (0, 0), &options);
+        let inner = Rect::new(111, 0, 178, 100);
+        for (i, &pixel) in buffer.iter().enumerate() {
+            let (x, y) = ((i % 400) as i32, (i / 400) as i32);
+            assert_eq!(pixel == LETTERBOX_COLOR, !inner.contains(x, y), "pixel ({x}, {y})");
+        }
+        assert_eq!(count_in(&buffer, 400, inner, BOUNDARY_COLOR), 178 * 100);
+    }
+
+    #[test]
+    fn region_outline_and_label_sit_at_its_rect() {
+        const RED: u32 = 0xFFFF0000;
+        let font = Font::builtin();
+        let region = NamedRegion { rect: Rect::new(10, 8, 60, 40), label: "AB".to_string(), color: RED, pinned: false };
+        let mut buffer = vec![0; 100 * 80];
+        draw_region(&mut buffer, 100, 80, &region, (500, 500), &font);
+
+        // The outline runs along the rect's outermost pixels, with the label just inside
+        let mut expected = vec![0; 100 * 80];
+        for (i, pixel) in expected.iter_mut().enumerate() {
+            let (x, y) = ((i % 100) as i32, (i / 100) as i32);
+            let inside = (10..70).contains(&x) && (8..48).contains(&y);
+            if inside && (x == 10 || x == 69 || y == 8 || y == 47) {
+                *pixel = RED;
+            }
+        }
+        let style = TextStyle { scale: 2, color: RED, ..TextStyle::default() };
+        draw_str(&mut expected, &font, "AB", 10 + REGION_LABEL_INSET, 8 + REGION_LABEL_INSET, 100, &style);
+        assert!(buffer == expected);
+
+        // Pinned to the world, it lands at the same place for a window at (100, 100)
+        let pinned = NamedRegion { rect: Rect::new(110, 108, 60, 40), pinned: true, ..region };
+        let mut buffer = vec![0; 100 * 80];
+        draw_region(&mut buffer, 100, 80, &pinned, (100, 100), &font);
+        assert!(buffer == expected);
+    }
+
+    #[test]
+    fn safe_area_outline_sits_at_the_inset() {
+        assert_eq!(safe_area_rect(200, 100, 5.0), Rect::new(10, 5, 180, 90));
+        let mut buffer = vec![0; 200 * 100];
+        draw_safe_area(&mut buffer, 200, 100, 5.0, SAFE_AREA_COLOR);
+        let at = |x: usize, y: usize| buffer[y * 200 + x] == SAFE_AREA_COLOR;
+        assert!(at(10, 5) && at(189, 5) && at(10, 94) && at(189, 94) && at(100, 5) && at(10, 50));
+        assert!(!at(9, 5) && !at(10, 4) && !at(11, 6) && !at(190, 95) && !at(100, 50));
+        // One pixel wide all round: 2 rows of 180 and 2 columns of 88 between them
+        assert_eq!(buffer.iter().filter(|&&pixel| pixel == SAFE_AREA_COLOR).count(), 2 * 180 + 2 * 88);
+    }
+
+    #[test]
+    fn huge_monitor_and_far_off_windows_do_not_overflow() {
+        let monitor_size = (u32::MAX, u32::MAX);
+        let options = RenderOptions { band_labels: true, show_magnifier: true, show_compass: true, ..RenderOptions::default() };
+        for window_pos in [(i32::MAX - 10, i32::MAX - 10), (i32::MIN, i32::MIN), (i32::MAX, i32::MIN), (0, 0)] {
+            let mut buffer = vec![0; 64 * 48];
+            render_frame(&mut buffer, 64, 48, window_pos, monitor_size, &options);
+            IncrementalRenderer::new().render(&mut buffer, 64, 48, window_pos, monitor_size, &options);
+        }
+        // Every world pixel of a window at the far corner is past the right and bottom
+        // edges of a 1920x1080 monitor
+        let mut buffer = vec![0; 64 * 48];
+        render_frame(&mut buffer, 64, 48, (i32::MAX - 10, i32::MAX - 10), (1920, 1080), &RenderOptions::default());
+        assert!(buffer.iter().all(|&pixel| pixel == BOUNDARY_COLOR));
+    }
+
+    #[test]
+    fn band_label_shows_only_for_active_edges() {
+        let labelled = RenderOptions { band_labels: true, ..RenderOptions::default() };
+        let text_in = |buffer: &[u32], area| count_in(buffer, 400, area, TEXT_COLOR);
+        // Against the top edge: the label is inside the top band, rows 0..100
+        let top = render(400, 300, (800, 0), &labelled);
+        assert!(text_in(&top, Rect::new(0, 0, 400, 100)) > 0);
+        assert_eq!(text_in(&top, Rect::new(0, 100, 400, 200)), 0);
+        // Mid-monitor no band is active, so there's nothing to label
+        assert_eq!(text_in(&render(400, 300, (800, 400), &labelled), Rect::new(0, 0, 400, 300)), 0);
+        assert_eq!(text_in(&render(400, 300, (800, 0), &RenderOptions::default()), Rect::new(0, 0, 400, 300)), 0);
+    }
+
+    #[test]
+    fn progress_bar_fills_its_fraction_and_clamps() {
+        const FG: u32 = 1;
+        const BG: u32 = 2;
+        // A 10x2 bar at (1, 1) in a 12x4 buffer
+        let filled = |fraction| {
+            let mut buffer = vec![0; 12 * 4];
+            draw_progress(&mut buffer, Rect::new(1, 1, 10, 2), fraction, FG, BG, 12, 4);
+            assert_eq!(buffer.iter().filter(|&&pixel| pixel == FG || pixel == BG).count(), 20);
+            let row: Vec<u32> = buffer[12 + 1..12 + 11].to_vec();
+            assert_eq!(row, buffer[24 + 1..24 + 11]);
+            row.iter().filter(|&&pixel| pixel == FG).count()
+        };
+        assert_eq!(filled(0.0), 0);
+        assert_eq!(filled(0.5), 5);
+        assert_eq!(filled(1.0), 10);
+        assert_eq!(filled(-3.0), 0);
+        assert_eq!(filled(7.5), 10);
+    }
+
+    #[test]
+    fn concentric_frames_color_each_ring() {
+        const OUTER: u32 = 0xFFFF0000;
+        const INNER: u32 = 0xFF0000FF;
+        let options = RenderOptions { frames: vec![(50, OUTER), (100, INNER)], ..RenderOptions::default() };
+        // A strip running in from the left edge, halfway down the monitor
+        let buffer = render(200, 10, (0, 500), &options);
+        assert_eq!(buffer[10], OUTER);
+        assert_eq!(buffer[49], OUTER);
+        assert_eq!(buffer[50], INNER);
+        assert_eq!(buffer[99], INNER);
+        assert_eq!(buffer[150], BACKGROUND);
+        assert_eq!(frame_color(&options.frames, -5), Some(OUTER));
+    }
+
+    #[test]
+    fn cycling_presets_wraps_and_applies_their_colors() {
+        let mut options = RenderOptions::default();
+        let mut index = 0;
+        for _ in 0..PRESETS.len() {
+            index = next_preset(index);
+            PRESETS[index].apply(&mut options);
+            assert_eq!(options.boundary_color, PRESETS[index].boundary);
+        }
+        // Back to green on black after a full cycle
+        assert_eq!(index, 0);
+        assert_eq!((options.background, options.boundary_color, options.text.color), (BACKGROUND, BOUNDARY_COLOR, TEXT_COLOR));
+        PRESETS[next_preset(0)].apply(&mut options);
+        assert_eq!((options.background, options.boundary_color, options.text.color), (0xFF000000, 0xFFFFB000, 0xFFFFE0A0));
+    }
+
+    #[test]
+    fn proximity_ratios_map_yellow_to_red() {
+        assert_eq!(proximity_ratio(100, 100), 0.0);
+        assert_eq!(proximity_ratio(50, 100), 0.5);
+        assert_eq!(proximity_ratio(-20, 100), 1.0);
+        assert_eq!(proximity_color(0.0), PROXIMITY_FAR_COLOR);
+        // Green fades out from 0xFF, red stays
+        assert_eq!(proximity_color(0.5), 0xFFFF8000);
+        assert_eq!(proximity_color(1.0), PROXIMITY_NEAR_COLOR);
+    }
+
+    #[test]
+    fn dithered_gradient_varies_between_neighbours_in_a_flat_region() {
+        const OUTER: u32 = 0xFF000000;
+        const INNER: u32 = 0xFF010101;
+        // Three quarters of the way from 0 to 1 in each channel, which plain rounding
+        // always turns into 1
+        let sample = |x, y| gradient_color(OUTER, INNER, 1, 2, Some((x, y)));
+        assert_eq!(gradient_color(OUTER, INNER, 1, 2, None), INNER);
+        assert_eq!((sample(0, 0), sample(1, 0)), (OUTER, INNER));
+        // The same pixel always gets the same shade, and the pattern tiles every 4 pixels
+        assert_eq!(sample(1, 0), sample(1, 0));
+        assert_eq!(sample(-3, 4), sample(1, 0));
+        // Across a tile the shades average out to the undithered value
+        let inner = (0..4).flat_map(|y| (0..4).map(move |x| (x, y))).filter(|&(x, y)| sample(x, y) == INNER).count();
+        assert_eq!(inner, 12);
+    }
+
+    #[test]
+    fn render_scale_is_fitted_at_the_pixel_cap() {
+        // 100x100 is exactly 10_000 pixels
+        assert_eq!(fit_render_scale(100, 100, 1, 10_000), Some(1));
+        assert_eq!(fit_render_scale(100, 100, 1, 9_999), Some(2));
+        // 1/2 scale rounds 101x101 up to 51x51
+        assert_eq!(fit_render_scale(101, 101, 1, 51 * 51), Some(2));
+        assert_eq!(fit_render_scale(101, 101, 1, 51 * 51 - 1), Some(3));
+        // A requested scale is kept if it already fits, never made finer
+        assert_eq!(fit_render_scale(100, 100, 4, 10_000), Some(4));
+        // Past the coarsest scale there is nothing left to fall back to
+        let coarsest = (1600 / MAX_RENDER_SCALE) as u64;
+        assert_eq!(fit_render_scale(1600, 1, 1, coarsest), Some(MAX_RENDER_SCALE));
+        assert_eq!(fit_render_scale(1600, 1, 1, coarsest - 1), None);
+    }
+
+    #[test]
+    fn monitor_edge_line_lands_on_the_window_column_of_world_x_zero() {
+        // World x == 0 is 30 columns into a window starting at x = -30; the other edges
+        // are out of view
+        assert_eq!(monitor_edge_lines(100, 50, (-30, 200), (1920, 1080)), (vec![30], vec![]));
+        let mut buffer = vec![0; 100 * 50];
+        draw_monitor_edges(&mut buffer, 100, 50, (-30, 200), (1920, 1080), EDGE_LINE_COLOR);
+        for (i, &pixel) in buffer.iter().enumerate() {
+            assert_eq!(pixel == EDGE_LINE_COLOR, i % 100 == 30, "pixel {i}");
+        }
+        // A window spanning a tiny monitor sees all four edges
+        assert_eq!(monitor_edge_lines(20, 20, (-5, -5), (10, 10)), (vec![5, 14], vec![5, 14]));
+    }
+
+    #[test]
+    fn reduced_resolution_upscales_into_blocks() {
+        let mut dst = vec![0; 5 * 5];
+        upscale_nearest(&[1, 2, 3, 4], 2, &mut dst, 5, 5, 3);
+        #[rustfmt::skip]
+        assert_eq!(dst, [
+            1, 1, 1, 2, 2,
+            1, 1, 1, 2, 2,
+            1, 1, 1, 2, 2,
+            3, 3, 3, 4, 4,
+            3, 3, 3, 4, 4,
+        ]);
+
+        // Straddling the inner edge of the left band, every 2x2 block is uniform
+        let options = RenderOptions { render_scale: 2, ..RenderOptions::default() };
+        let buffer = render(20, 10, (90, 400), &options);
+        for (i, &pixel) in buffer.iter().enumerate() {
+            let (x, y) = (i % 20, i / 20);
+            assert_eq!(pixel, buffer[(y & !1) * 20 + (x & !1)], "pixel ({x}, {y})");
+        }
+        assert_eq!(buffer[0], BOUNDARY_COLOR);
+        assert_eq!(buffer[19], BACKGROUND);
+    }
+
+    #[test]
+    fn incremental_move_matches_a_full_redraw() {
+        for boundary_mode in [BoundaryMode::Flat, BoundaryMode::Proximity, BoundaryMode::Gradient] {
+            let options = RenderOptions { boundary_mode, ..RenderOptions::default() };
+            let mut renderer = IncrementalRenderer::new();
+            let mut buffer = vec![0; 200 * 150];
+            // In the top-left corner, then a small move that shrinks the left band and
+            // grows the top one
+            renderer.render(&mut buffer, 200, 150, (40, 30), (1920, 1080), &options);
+            renderer.render(&mut buffer, 200, 150, (47, 27), (1920, 1080), &options);
+            assert!(buffer == render(200, 150, (47, 27), &options), "{boundary_mode:?}");
+        }
+
+        // Edge pixels, from a window straddling the top-left corner to one clear of it.
+        // Without bands to repaint, they still have to move with the window.
+        for boundary_size in [BOUNDARY_SIZE, 0] {
+            let options = RenderOptions { boundary_mode: BoundaryMode::EdgeOnly, boundary_size, ..RenderOptions::default() };
+            let mut renderer = IncrementalRenderer::new();
+            let mut buffer = vec![0; 200 * 150];
+            for window_pos in [(-5, -3), (-8, -1), (40, 30)] {
+                renderer.render(&mut buffer, 200, 150, window_pos, (1920, 1080), &options);
+                assert!(buffer == render(200, 150, window_pos, &options), "EdgeOnly at {window_pos:?}, boundary size {boundary_size}");
+            }
+        }
+    }
+
+    #[test]
+    fn edge_only_lights_just_the_column_at_world_x_zero() {
+        let options = RenderOptions { boundary_mode: BoundaryMode::EdgeOnly, ..RenderOptions::default() };
+        let buffer = render(200, 100, (0, 400), &options);
+        for (i, &pixel) in buffer.iter().enumerate() {
+            assert_eq!(pixel == BOUNDARY_COLOR, i % 200 == 0, "pixel {i}");
+        }
+        // Inside the band but not on the edge itself: nothing
+        assert!(!render(200, 100, (1, 400), &options).contains(&BOUNDARY_COLOR));
+    }
+
+    #[test]
+    fn boundary_size_env_values_parse_and_clamp() {
+        assert_eq!(parse_boundary_size("150"), Some(150));
+        assert_eq!(parse_boundary_size(" 150\n"), Some(150));
+        assert_eq!(parse_boundary_size("-5"), Some(0));
+        assert_eq!(parse_boundary_size("999999"), Some(MAX_BOUNDARY_SIZE));
+        assert_eq!(parse_boundary_size("wide"), None);
+        assert_eq!(parse_boundary_size(""), None);
+    }
+
+    #[test]
+    fn pinned_label_moves_opposite_to_the_window() {
+        let label = Label { text: "HI".to_string(), x: 500, y: 300, style: TextStyle { scale: 1, ..TextStyle::default() }, pinned: true };
+        assert_eq!(label.window_position((400, 200)), (100, 100));
+        assert_eq!(label.window_position((430, 210)), (70, 90));
+        assert_eq!(Label { pinned: false, ..label.clone() }.window_position((430, 210)), (500, 300));
+
+        let options = RenderOptions { labels: vec![label], ..RenderOptions::default() };
+        let lit = |window_pos| {
+            let buffer = render(200, 150, window_pos, &options);
+            buffer.iter().position(|&pixel| pixel == TEXT_COLOR).map(|i| (i % 200, i / 200))
+        };
+        let (before, after) = (lit((400, 200)).unwrap(), lit((430, 210)).unwrap());
+        assert_eq!((before.0 - after.0, before.1 - after.1), (30, 10));
+        // Out of the window once it has moved past
+        assert_eq!(lit((600, 200)), None);
+    }
+
+    #[test]
+    fn font_grid_labels_each_glyph_with_its_character() {
+        assert_eq!(font_grid_label('A'), "A");
+        assert_eq!(font_grid_label('é'), "é");
+        let (width, height) = (400, 300);
+        let mut buffer = vec![0; (width * height) as usize];
+        draw_font_grid(&mut buffer, width, height);
+        let (scale, _) = font_grid_layout(font_grid_chars().len(), width, height);
+        assert!(scale > 1);
+
+        // The first cell: 'A' at the grid scale, then the same letter at 1x under it
+        let style = TextStyle { scale, ..TextStyle::default() };
+        let mut expected = vec![0; (width * height) as usize];
+        draw_char(&mut expected, FONT_GRID_PADDING, FONT_GRID_PADDING, &BUILTIN_FONT, 'A', width, &style);
+        let label_y = FONT_GRID_PADDING + HEIGHT * scale + FONT_GRID_PADDING / 2;
+        draw_str(&mut expected, &BUILTIN_FONT, "A", FONT_GRID_PADDING, label_y, width, &TextStyle { scale: 1, ..TextStyle::default() });
+        let cell = Rect::new(0, 0, font_grid_cell_width(scale), label_y + HEIGHT);
+        for y in cell.y..cell.bottom() {
+            for x in cell.x..cell.right() {
+                let i = (y as u32 * width + x as u32) as usize;
+                assert_eq!(buffer[i], expected[i], "pixel ({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn labels_draw_at_their_own_positions() {
+        const RED: u32 = 0xFFFF0000;
+        const BLUE: u32 = 0xFF0000FF;
+        let label = |text: &str, x, y, color| Label {
+            text: text.to_string(),
+            x,
+            y,
+            style: TextStyle { scale: 1, color, ..TextStyle::default() },
+            pinned: false,
+        };
+        let options = RenderOptions { labels: vec![label("AB", 10, 10, RED), label("CD", 100, 50, BLUE)], ..RenderOptions::default() };
+        // Mid-monitor, so no bands or hidden text get in the way
+        let buffer = render(200, 100, (800, 400), &options);
+        let first = Rect::new(10, 10, 2 * ADVANCE, HEIGHT);
+        let second = Rect::new(100, 50, 2 * ADVANCE, HEIGHT);
+        assert!(count_in(&buffer, 200, first, RED) > 0);
+        assert!(count_in(&buffer, 200, second, BLUE) > 0);
+        assert_eq!(count_in(&buffer, 200, Rect::new(0, 0, 200, 100), RED), count_in(&buffer, 200, first, RED));
+        assert_eq!(count_in(&buffer, 200, Rect::new(0, 0, 200, 100), BLUE), count_in(&buffer, 200, second, BLUE));
+    }
+}
@@ -0,0 +1,129 @@
+//! Deciding when the event loop next has to wake up on its own
+//!
+//! Every timed feature (the `--once` exit, the typewriter reveal, replayed moves, ...)
+//! reports when it next needs attention, and `next_wake` arbitrates between them. With
+//! nothing pending the loop sleeps until the next event, so a static window uses no CPU.
+//!
+//! Animations read the time from an `AnimationClock` rather than `Instant::now()`, so
+//! slow motion stretches all of them alike.
+
+use std::time::{Duration, Instant};
+
+/// Slow-motion factors the clock cycles through
+pub const TIME_SCALES: [f32; 3] = [1.0, 2.0, 4.0];
+
+/// Animation time, which runs `time_scale` times slower than the wall clock. Readings are
+/// `Instant`s so animations can keep storing plain start times and deadlines.
+#[derive(Debug, Clone, Copy)]
+pub struct AnimationClock {
+    /// Wall-clock time of the last change of scale
+    wall_anchor: Instant,
+    /// Animation time at `wall_anchor`
+    anim_anchor: Instant,
+    time_scale: f32,
+}
+
+impl AnimationClock {
+    /// A clock running at normal speed, reading `now` at `now`
+    pub const fn new(now: Instant) -> Self {
+        Self { wall_anchor: now, anim_anchor: now, time_scale: 1.0 }
+    }
+
+    pub const fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    /// Animation time at wall-clock `now`
+    pub fn at(&self, now: Instant) -> Instant {
+        self.anim_anchor + now.saturating_duration_since(self.wall_anchor).div_f32(self.time_scale)
+    }
+
+    /// Wall-clock time at which the clock reads `anim`, for scheduling wake-ups
+    pub fn wall_time(&self, anim: Instant) -> Instant {
+        self.wall_anchor + anim.saturating_duration_since(self.anim_anchor).mul_f32(self.time_scale)
+    }
+
+    /// Changes speed from `now` on, without jumping: animation time carries on from
+    /// where it was
+    pub fn set_time_scale(&mut self, now: Instant, time_scale: f32) {
+        self.anim_anchor = self.at(now);
+        self.wall_anchor = now;
+        self.time_scale = time_scale.max(f32::EPSILON);
+    }
+
+    /// Moves to the next of `TIME_SCALES`, wrapping back to normal speed
+    pub fn cycle_time_scale(&mut self, now: Instant) {
+        let next = TIME_SCALES.iter().position(|&scale| scale == self.time_scale).map_or(0, |index| (index + 1) % TIME_SCALES.len());
+        self.set_time_scale(now, TIME_SCALES[next]);
+    }
+}
+
+/// How long an animation has been running at wall-clock `now`, in animation time
+pub fn anim_elapsed(clock: &AnimationClock, start: Instant, now: Instant) -> Duration {
+    clock.at(now).saturating_duration_since(start)
+}
+
+/// When to wake up without an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wake {
+    /// Nothing is animating; only events wake the loop
+    Idle,
+    /// The earliest pending deadline
+    At(Instant),
+    /// A deadline has already passed, so go round again straight away
+    Now,
+}
+
+/// Picks the earliest of `deadlines` (`None` for features that are inactive)
+pub fn next_wake(now: Instant, deadlines: impl IntoIterator<Item = Option<Instant>>) -> Wake {
+    match deadlines.into_iter().flatten().min() {
+        None => Wake::Idle,
+        Some(at) if at <= now => Wake::Now,
+        Some(at) => Wake::At(at),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wake_follows_the_earliest_active_deadline() {
+        let now = Instant::now();
+        let soon = now + Duration::from_millis(16);
+        let later = now + Duration::from_secs(2);
+        // Nothing animating: sleep until an event
+        assert_eq!(next_wake(now, []), Wake::Idle);
+        assert_eq!(next_wake(now, [None, None]), Wake::Idle);
+        // One or more animations: the earliest deadline wins, inactive ones are ignored
+        assert_eq!(next_wake(now, [None, Some(later)]), Wake::At(later));
+        assert_eq!(next_wake(now, [Some(later), None, Some(soon)]), Wake::At(soon));
+        // A deadline that is due or overdue keeps the loop running
+        assert_eq!(next_wake(now, [Some(later), Some(now)]), Wake::Now);
+        assert_eq!(next_wake(soon, [Some(now)]), Wake::Now);
+    }
+
+    #[test]
+    fn slow_motion_scales_animation_time() {
+        let start = Instant::now();
+        let second = Duration::from_secs(1);
+        let mut clock = AnimationClock::new(start);
+        assert_eq!(anim_elapsed(&clock, start, start + second), second);
+        // At 4x slow motion a wall-clock second is a quarter of an animation second
+        clock.set_time_scale(start, 4.0);
+        assert_eq!(anim_elapsed(&clock, start, start + second), second / 4);
+        assert_eq!(clock.wall_time(start + second), start + 4 * second);
+
+        // Changing speed mid-animation keeps the time already elapsed
+        let mut clock = AnimationClock::new(start);
+        clock.set_time_scale(start + second, 2.0);
+        assert_eq!(anim_elapsed(&clock, start, start + 3 * second), 2 * second);
+        assert_eq!(clock.wall_time(start + 2 * second), start + 3 * second);
+
+        // Cycling goes on from 2x to 4x, then wraps back to normal speed
+        for expected in [4.0, 1.0, 2.0] {
+            clock.cycle_time_scale(start + 3 * second);
+            assert_eq!(clock.time_scale(), expected);
+        }
+    }
+}
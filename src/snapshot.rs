@@ -0,0 +1,113 @@
+//! Rendering frames without a window, to sample or to save as PNG files
+
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+
+use crate::color::argb_to_rgba;
+use crate::geometry::world_to_window;
+use crate::render::{RenderOptions, render_frame};
+
+/// Writes `width` x `height` `0xAARRGGBB` pixels to `path` as an RGBA PNG
+pub fn write_png(path: &Path, width: u32, height: u32, pixels: &[u32]) -> io::Result<()> {
+    let rgba: Vec<u8> = pixels.iter().flat_map(|&color| argb_to_rgba(color)).collect();
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(&rgba)?;
+    Ok(())
+}
+
+/// A finished frame, kept along with where its window was
+#[derive(Debug, Clone)]
+pub struct RenderedFrame {
+    pub pixels: Vec<u32>,
+    pub width: u32,
+    pub height: u32,
+    pub window_pos: (i32, i32),
+}
+
+impl RenderedFrame {
+    /// Renders the frame a `size` window at `window_pos` would show
+    pub fn render(size: (u32, u32), window_pos: (i32, i32), monitor_size: (u32, u32), options: &RenderOptions) -> Self {
+        let mut pixels = vec![options.background; (size.0 * size.1) as usize];
+        render_frame(&mut pixels, size.0, size.1, window_pos, monitor_size, options);
+        Self { pixels, width: size.0, height: size.1, window_pos }
+    }
+
+    /// The pixel showing world coordinate `(wx, wy)`, or `None` if the window doesn't
+    /// cover it
+    pub fn sample_world_pixel(&self, wx: i32, wy: i32) -> Option<u32> {
+        let (x, y) = world_to_window((wx, wy), self.window_pos);
+        if x < 0 || y < 0 || x as u32 >= self.width || y as u32 >= self.height {
+            return None;
+        }
+        Some(self.pixels[(y as u32 * self.width + x as u32) as usize])
+    }
+}
+
+/// Renders the frame a `size` window at `window_pos` would show and writes it to `path`
+pub fn export_frame(path: &Path, size: (u32, u32), window_pos: (i32, i32), monitor_size: (u32, u32), options: &RenderOptions) -> io::Result<()> {
+    let frame = RenderedFrame::render(size, window_pos, monitor_size, options);
+    write_png(path, frame.width, frame.height, &frame.pixels)
+}
+
+/// What became of a frame that couldn't be shown because no window surface could be made
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fallback {
+    /// Written to this PNG instead
+    Png(PathBuf),
+    /// No `--fallback-png` path was given, so there is nowhere to put it
+    Unconfigured,
+}
+
+/// Falls back from a failed window surface to `export_frame` into `fallback_png`, if one
+/// was configured
+pub fn export_fallback(
+    fallback_png: Option<&Path>,
+    size: (u32, u32),
+    window_pos: (i32, i32),
+    monitor_size: (u32, u32),
+    options: &RenderOptions,
+) -> io::Result<Fallback> {
+    let Some(path) = fallback_png else {
+        return Ok(Fallback::Unconfigured);
+    };
+    export_frame(path, (size.0.max(1), size.1.max(1)), window_pos, monitor_size, options)?;
+    Ok(Fallback::Png(path.to_path_buf()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{BACKGROUND, BOUNDARY_COLOR};
+
+    #[test]
+    fn sampling_world_pixels_of_a_rendered_frame() {
+        // A 400x300 window on the left edge of the monitor, half way down
+        let frame = RenderedFrame::render((400, 300), (0, 400), (1920, 1080), &RenderOptions::default());
+        // Inside the left band, and well clear of every band
+        assert_eq!(frame.sample_world_pixel(5, 500), Some(BOUNDARY_COLOR));
+        assert_eq!(frame.sample_world_pixel(300, 550), Some(BACKGROUND));
+        // The corners of the window map to the corners of the buffer
+        assert_eq!(frame.sample_world_pixel(399, 699), Some(frame.pixels[frame.pixels.len() - 1]));
+        // Just outside the window on each side
+        for (wx, wy) in [(-1, 500), (400, 500), (200, 399), (200, 700)] {
+            assert_eq!(frame.sample_world_pixel(wx, wy), None, "({wx}, {wy})");
+        }
+    }
+
+    #[test]
+    fn surface_failure_falls_back_to_the_configured_png() {
+        let options = RenderOptions::default();
+        assert_eq!(export_fallback(None, (64, 48), (0, 0), (1920, 1080), &options).unwrap(), Fallback::Unconfigured);
+
+        let path = std::env::temp_dir().join(format!("window-messing-fallback-{}.png", std::process::id()));
+        // A minimized window has no size, but the fallback still writes a frame
+        let outcome = export_fallback(Some(&path), (0, 48), (0, 0), (1920, 1080), &options);
+        let decoded = png::Decoder::new(File::open(&path).unwrap()).read_info().map(|reader| (reader.info().width, reader.info().height));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(outcome.unwrap(), Fallback::Png(path));
+        assert_eq!(decoded.unwrap(), (1, 48));
+    }
+}
@@ -0,0 +1,92 @@
+//! A status bar along the bottom of the window, split into labelled cells
+
+use crate::font::Font;
+use crate::geometry::Rect;
+use crate::render::shade_rect;
+use crate::text::{Align, TextStyle, draw_str_aligned, line_advance};
+
+/// Backdrop of the bar, blended over the frame
+pub const STATUS_BAR_COLOR: u32 = 0xFF000000;
+pub const STATUS_BAR_ALPHA: u8 = 192;
+/// Space around the text, above and below and between cells
+pub const STATUS_BAR_PADDING: i32 = 4;
+
+/// One segment of the bar
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusCell {
+    pub text: String,
+    /// Share of the bar width relative to the other cells' weights
+    pub weight: u32,
+    pub align: Align,
+}
+
+/// Splits `bar` left to right into one rect per weight, proportional to the weights.
+/// The rects tile `bar` exactly: rounding is spread so the last one ends at its right edge.
+pub fn cell_layout(bar: Rect, weights: &[u32]) -> Vec<Rect> {
+    let total: u64 = weights.iter().map(|&weight| weight as u64).sum();
+    if total == 0 {
+        return weights.iter().map(|_| Rect::new(bar.x, bar.y, 0, bar.height)).collect();
+    }
+    let edge = |sum: u64| bar.x + (bar.width.max(0) as u64 * sum / total) as i32;
+    let mut sum = 0;
+    weights
+        .iter()
+        .map(|&weight| {
+            let left = edge(sum);
+            sum += weight as u64;
+            Rect::new(left, bar.y, edge(sum) - left, bar.height)
+        })
+        .collect()
+}
+
+/// Height of a bar holding one line of text in `style`
+pub fn status_bar_height(font: &Font, style: &TextStyle) -> i32 {
+    line_advance(font, style) - style.line_spacing + 2 * STATUS_BAR_PADDING
+}
+
+/// Draws the bar across the bottom of the window with each of `cells` in its share of
+/// the width, text clipped to the cell
+pub fn draw_status_bar(buffer: &mut [u32], width: u32, height: u32, font: &Font, cells: &[StatusCell], style: &TextStyle) {
+    let bar_height = status_bar_height(font, style);
+    let bar = Rect::new(0, height as i32 - bar_height, width as i32, bar_height);
+    shade_rect(buffer, width, height, bar, STATUS_BAR_COLOR, STATUS_BAR_ALPHA);
+    let weights: Vec<_> = cells.iter().map(|cell| cell.weight).collect();
+    for (cell, rect) in cells.iter().zip(cell_layout(bar, &weights)) {
+        let inner = Rect::new(rect.x + STATUS_BAR_PADDING, rect.y, rect.width - 2 * STATUS_BAR_PADDING, rect.height);
+        draw_str_aligned(buffer, font, &cell.text, inner, cell.align, width, style);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::TEXT_COLOR;
+
+    #[test]
+    fn three_cells_tile_the_bar_and_clip_their_text() {
+        // Uneven division spreads the rounding but still covers the bar exactly
+        let cells = cell_layout(Rect::new(10, 5, 100, 12), &[1, 1, 1]);
+        assert_eq!(cells.iter().map(|cell| (cell.x, cell.width)).collect::<Vec<_>>(), [(10, 33), (43, 33), (76, 34)]);
+        assert!(cells.iter().all(|cell| cell.y == 5 && cell.height == 12));
+        let weighted = cell_layout(Rect::new(0, 0, 120, 12), &[1, 2, 1]);
+        assert_eq!(weighted.iter().map(|cell| cell.width).collect::<Vec<_>>(), [30, 60, 30]);
+
+        // Text far too long for its cell stays inside the cell's padding
+        let font = Font::builtin();
+        let style = TextStyle { scale: 1, ..TextStyle::default() };
+        let long = "M".repeat(20);
+        let cells: Vec<_> = [Align::Left, Align::Center, Align::Right]
+            .into_iter()
+            .map(|align| StatusCell { text: long.clone(), weight: 1, align })
+            .collect();
+        let (width, height) = (120, 30);
+        let mut buffer = vec![0; (width * height) as usize];
+        draw_status_bar(&mut buffer, width, height, &font, &cells, &style);
+        let columns: Vec<_> = (0..width as usize).filter(|&x| buffer.iter().skip(x).step_by(width as usize).any(|&pixel| pixel == TEXT_COLOR)).collect();
+        for cell in 0..3 {
+            let inner = cell * 40 + STATUS_BAR_PADDING as usize..(cell + 1) * 40 - STATUS_BAR_PADDING as usize;
+            assert!(columns.iter().any(|x| inner.contains(x)), "cell {cell} drew nothing");
+        }
+        assert!(columns.iter().all(|x| (STATUS_BAR_PADDING as usize..40 - STATUS_BAR_PADDING as usize).contains(&(x % 40))));
+    }
+}
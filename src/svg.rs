@@ -0,0 +1,87 @@
+//! Vector export of the boundary layout, for crisp documentation images
+
+use std::fmt::Write;
+
+use crate::geometry::{Rect, visible_bands};
+use crate::render::RenderOptions;
+
+/// Renders the monitor, the window, and the boundary bands overlapping the window as SVG.
+///
+/// Everything is in world coordinates; the view box covers both the monitor and the
+/// window so off-screen windows are still visible.
+pub fn render_svg(
+    window_pos: (i32, i32),
+    inner_size: (u32, u32),
+    monitor_size: (u32, u32),
+    options: &RenderOptions,
+) -> String {
+    const MARGIN: i32 = 20;
+    let window = Rect::new(window_pos.0, window_pos.1, inner_size.0 as i32, inner_size.1 as i32);
+    let monitor = Rect::new(0, 0, monitor_size.0 as i32, monitor_size.1 as i32);
+
+    let min_x = monitor.x.min(window.x) - MARGIN;
+    let min_y = monitor.y.min(window.y) - MARGIN;
+    let max_x = monitor.right().max(window.right()) + MARGIN;
+    let max_y = monitor.bottom().max(window.bottom()) + MARGIN;
+
+    let mut svg = String::new();
+    // Writing into a String cannot fail
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{min_x} {min_y} {} {}" width="{}" height="{}">"#,
+        max_x - min_x,
+        max_y - min_y,
+        max_x - min_x,
+        max_y - min_y,
+    );
+    let _ = writeln!(
+        svg,
+        r##"  <rect x="{}" y="{}" width="{}" height="{}" fill="#202020" stroke="#808080"/>"##,
+        monitor.x, monitor.y, monitor.width, monitor.height,
+    );
+    let _ = writeln!(
+        svg,
+        r##"  <rect x="{}" y="{}" width="{}" height="{}" fill="#000000" stroke="#ffffff" stroke-width="2"/>"##,
+        window.x, window.y, window.width, window.height,
+    );
+    for (edge, band) in visible_bands(window, monitor_size, options.boundary_size) {
+        let _ = writeln!(
+            svg,
+            r##"  <rect x="{}" y="{}" width="{}" height="{}" fill="#00ff00"/>"##,
+            band.x, band.y, band.width, band.height,
+        );
+        let _ = writeln!(
+            svg,
+            r##"  <text x="{}" y="{}" fill="#000000" font-family="monospace" font-size="14">{}</text>"##,
+            band.x + 4,
+            band.y + 16,
+            edge.name(),
+        );
+    }
+    let (report_x, report_y) = options.origin.window_position(window, monitor.height);
+    let _ = writeln!(
+        svg,
+        r##"  <text x="{}" y="{}" fill="#ffffff" font-family="monospace" font-size="14">window ({}, {}) {}x{}</text>"##,
+        window.x,
+        window.y - 6,
+        report_x,
+        report_y,
+        window.width,
+        window.height,
+    );
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corner_window_has_monitor_window_and_two_band_rects() {
+        let svg = render_svg((0, 0), (400, 300), (1920, 1080), &RenderOptions::default());
+        // Monitor, window, and the left and top bands
+        assert_eq!(svg.matches("<rect ").count(), 4);
+        assert!(svg.starts_with("<svg ") && svg.ends_with("</svg>\n"));
+    }
+}
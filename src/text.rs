@@ -0,0 +1,761 @@
+//! Bitmap text layout and drawing
+
+use crate::color::{blend_pixel, contrasting};
+use crate::geometry::{Rect, saturate};
+use crate::font::{BUILTIN_FONT, BakedText, Font, GLYPH_ADVANCE, GLYPH_HEIGHT, GLYPH_WIDTH, KERNING};
+use crate::render::{BASE_TEXT_SCALE, TEXT_COLOR, draw_line};
+
+pub use crate::font::Glyph;
+
+/// `GLYPH_WIDTH` in font pixels, for pixel math
+pub const WIDTH: i32 = GLYPH_WIDTH as i32;
+/// `GLYPH_HEIGHT` in font pixels, for pixel math
+pub const HEIGHT: i32 = GLYPH_HEIGHT as i32;
+/// `GLYPH_ADVANCE` in font pixels, for pixel math
+pub const ADVANCE: i32 = GLYPH_ADVANCE as i32;
+
+
+/// How a run of text is drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextStyle {
+    /// Integer block size for each font pixel
+    pub scale: i32,
+    pub color: u32,
+    /// Draw a line under the run
+    pub underline: bool,
+    /// Advance by each glyph's inked width, adjusted by `KERNING`, instead of a fixed cell
+    pub proportional: bool,
+    /// Ignore `color` and draw each pixel black or white, whichever contrasts with the
+    /// pixel already underneath it
+    pub auto_contrast: bool,
+    /// Extra pixels between lines of multi-line text on top of the cell height; negative
+    /// values overlap the lines
+    pub line_spacing: i32,
+    /// Draw the run first at an `(dx, dy)` pixel offset in this color, then normally on
+    /// top, for a drop shadow
+    pub shadow: Option<(i32, i32, u32)>,
+    /// Snap the hidden text's origin to a multiple of `scale` so its blocks sit on the
+    /// window's `scale`-pixel grid
+    pub snap: bool,
+    /// Soften the stair-steps of diagonal strokes with partly transparent corner fills
+    pub aa_diagonals: bool,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self {
+            scale: BASE_TEXT_SCALE,
+            color: TEXT_COLOR,
+            underline: false,
+            proportional: false,
+            auto_contrast: false,
+            line_spacing: 0,
+            shadow: None,
+            snap: false,
+            aa_diagonals: false,
+        }
+    }
+}
+
+/// Width of the inked columns of a `cell_width` wide glyph, 0 if it's blank
+fn ink_width(pixels: &[bool], cell_width: usize) -> i32 {
+    pixels
+        .chunks_exact(cell_width)
+        .filter_map(|row| row.iter().rposition(|&pixel| pixel))
+        .max()
+        .map_or(0, |col| col as i32 + 1)
+}
+
+/// Advance used for blank glyphs (e.g. space) in proportional mode, before spacing: 3
+/// for the built-in font
+fn blank_width(font: &Font) -> i32 {
+    (font.cell_width as i32 * 3 / 5).max(1)
+}
+
+/// Advance adjustment in font pixels for `left` followed by `right`
+pub fn kerning(left: char, right: char) -> i32 {
+    KERNING
+        .iter()
+        .find(|&&((a, b), _)| (char::from(a), char::from(b)) == (left, right))
+        .map_or(0, |&(_, adjustment)| adjustment)
+}
+
+/// Distance in font pixels from the start of `c` to the start of the glyph after it.
+/// Characters without a glyph get a full cell, for their tofu box.
+pub fn glyph_advance(font: &Font, c: char, next: Option<char>, proportional: bool) -> i32 {
+    if !proportional {
+        return font.advance() as i32;
+    }
+    let width = match font.glyph(c).map_or(font.cell_width as i32, |glyph| ink_width(glyph, font.cell_width)) {
+        0 => blank_width(font),
+        width => width,
+    };
+    width + 1 + next.map_or(0, |next| kerning(c, next))
+}
+
+/// The x offset of each character's glyph from the start of the run, followed by the
+/// offset just past the run, in pixels
+pub fn glyph_offsets(font: &Font, text: &str, style: &TextStyle) -> Vec<i32> {
+    char_offsets(font, &text.chars().collect::<Vec<_>>(), style)
+}
+
+/// `glyph_offsets` for text already split into characters
+fn char_offsets(font: &Font, text: &[char], style: &TextStyle) -> Vec<i32> {
+    let mut offsets = Vec::with_capacity(text.len() + 1);
+    let mut x = 0;
+    for (i, &c) in text.iter().enumerate() {
+        offsets.push(x);
+        x += glyph_advance(font, c, text.get(i + 1).copied(), style.proportional) * style.scale;
+    }
+    offsets.push(x);
+    offsets
+}
+
+/// Size in pixels of `len` fixed-width glyphs of the built-in font drawn at `scale`,
+/// excluding the trailing spacing
+pub const fn measure_text(len: usize, scale: i32) -> (i32, i32) {
+    if len == 0 {
+        return (0, HEIGHT * scale);
+    }
+    (len as i32 * ADVANCE * scale - scale, HEIGHT * scale)
+}
+
+/// Distance in pixels from the top of one line of text to the top of the next
+pub fn line_advance(font: &Font, style: &TextStyle) -> i32 {
+    font.cell_height as i32 * style.scale + style.line_spacing
+}
+
+/// Size in pixels of `text` drawn in `font` with `style`, excluding the trailing spacing.
+/// Text with `\n` is measured as stacked lines: the widest line by the span of them all.
+pub fn measure_str(font: &Font, text: &str, style: &TextStyle) -> (i32, i32) {
+    let (count, width) = text.split('\n').fold((0, 0), |(count, width), line| {
+        let chars: Vec<char> = line.chars().collect();
+        (count + 1, width.max(measure_line(font, &chars, style).0))
+    });
+    let height = font.cell_height as i32 * style.scale + (count - 1) * line_advance(font, style);
+    (width, height.max(0))
+}
+
+/// `measure_str` for a single line
+fn measure_line(font: &Font, text: &[char], style: &TextStyle) -> (i32, i32) {
+    let height = font.cell_height as i32 * style.scale;
+    if text.is_empty() {
+        return (0, height);
+    }
+    if !style.proportional {
+        let width = text.len() as i32 * font.advance() as i32 - font.spacing as i32;
+        return (width * style.scale, height);
+    }
+    let total = char_offsets(font, text, style)[text.len()];
+    ((total - style.scale).max(0), height)
+}
+
+/// Indices of the glyphs in a fixed-width run of `count` cells of `font` starting at
+/// `(x, y)` that overlap the buffer at all. Glyphs wholly outside it are never visited,
+/// which matters for the hidden text whose origin is usually thousands of pixels away.
+pub fn visible_glyph_range(font: &Font, x: i32, y: i32, count: usize, scale: i32, buffer_width: u32, buffer_height: u32) -> std::ops::Range<usize> {
+    let advance = font.advance() as i64 * scale as i64;
+    let glyph_width = font.cell_width as i64 * scale as i64;
+    let (x, y) = (x as i64, y as i64);
+    if y + font.cell_height as i64 * scale as i64 <= 0 || y >= buffer_height as i64 {
+        return 0..0;
+    }
+    // First glyph whose right edge is past x = 0
+    let first = if x + glyph_width > 0 { 0 } else { (-x - glyph_width) / advance + 1 };
+    // Glyphs starting before the right edge of the buffer
+    let end = if (buffer_width as i64) <= x { 0 } else { (buffer_width as i64 - x + advance - 1) / advance };
+    let first = first.clamp(0, count as i64) as usize;
+    let end = end.clamp(0, count as i64) as usize;
+    first..end.max(first)
+}
+
+/// Draws a run whose glyph `i` is `glyph(i)` and whose characters (for advances) are `text`,
+/// laid out with the cell size of `font`. Returns the x coordinate just past the run.
+fn draw_run<'a>(
+    buffer: &mut [u32],
+    buffer_width: u32,
+    (x, y): (i32, i32),
+    font: &Font,
+    text: &[char],
+    glyph: &dyn Fn(usize) -> Option<&'a [bool]>,
+    style: &TextStyle,
+) -> i32 {
+    if let Some((dx, dy, color)) = style.shadow {
+        let shadow_style = TextStyle { color, auto_contrast: false, shadow: None, ..*style };
+        draw_run(buffer, buffer_width, (x.saturating_add(dx), y.saturating_add(dy)), font, text, glyph, &shadow_style);
+    }
+    let buffer_height = buffer.len() as u32 / buffer_width.max(1);
+    let scale = style.scale;
+    let end = if style.proportional {
+        let offsets = char_offsets(font, text, style);
+        for (i, &offset) in offsets[..text.len()].iter().enumerate() {
+            let glyph_x = x.saturating_add(offset);
+            // Cull whole glyphs; per-pixel clipping in draw_cell handles the rest
+            if glyph_x.saturating_add(font.cell_width as i32 * scale) <= 0 || glyph_x >= buffer_width as i32 {
+                continue;
+            }
+            if let Some(glyph) = glyph(i) {
+                draw_cell(buffer, glyph_x, y, glyph, font.cell_width, buffer_width, style);
+            }
+        }
+        x.saturating_add(offsets[text.len()])
+    } else {
+        let advance = font.advance() as i32 * scale;
+        for i in visible_glyph_range(font, x, y, text.len(), scale, buffer_width, buffer_height) {
+            if let Some(glyph) = glyph(i) {
+                draw_cell(buffer, x + i as i32 * advance, y, glyph, font.cell_width, buffer_width, style);
+            }
+        }
+        x.saturating_add(text.len() as i32 * advance)
+    };
+    if style.underline {
+        let (text_width, text_height) = measure_line(font, text, style);
+        draw_underline(buffer, buffer_width, x, y.saturating_add(text_height), text_width, scale, style.color);
+    }
+    end
+}
+
+/// The multiple of `scale` nearest to `coord`, halves rounding up (towards +∞)
+pub const fn snap_to_scale(coord: i32, scale: i32) -> i32 {
+    if scale <= 1 {
+        return coord;
+    }
+    saturate((coord as i64 + scale as i64 / 2).div_euclid(scale as i64) * scale as i64)
+}
+
+/// Draws baked `text` at `(x, y)`, or only its first `reveal_chars` glyphs. A partial
+/// run is laid out exactly like the start of the full one.
+pub fn draw_text(buffer: &mut [u32], text: &BakedText, x: i32, y: i32, buffer_width: u32, style: &TextStyle, reveal_chars: Option<usize>) {
+    let (x, y) = if style.snap { (snap_to_scale(x, style.scale), snap_to_scale(y, style.scale)) } else { (x, y) };
+    let shown = reveal_chars.map_or(text.source.len(), |n| n.min(text.source.len()));
+    let chars: Vec<char> = text.source[..shown].iter().map(|&byte| char::from(byte)).collect();
+    let glyph = |i: usize| text.bitmaps.get(i).map(|glyph| glyph.as_flattened());
+    draw_run(buffer, buffer_width, (x, y), &BUILTIN_FONT, &chars, &glyph, style);
+}
+
+/// Draws runtime text, looking each character up in `font`. Characters without a glyph
+/// are drawn as `Font::tofu` boxes. Each `\n` starts a new line at `x`, `line_advance`
+/// further down. Returns the x coordinate just past the last glyph of the last line.
+pub fn draw_str(buffer: &mut [u32], font: &Font, text: &str, x: i32, y: i32, buffer_width: u32, style: &TextStyle) -> i32 {
+    let mut end = x;
+    for (i, line) in text.split('\n').enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        let line_y = saturate(y as i64 + i as i64 * line_advance(font, style) as i64);
+        end = draw_run(buffer, buffer_width, (x, line_y), font, &chars, &|i| Some(font.glyph(chars[i]).unwrap_or(font.tofu())), style);
+    }
+    end
+}
+
+/// Distance in pixels from the top of one glyph of vertical text to the top of the next
+fn vertical_advance(font: &Font, style: &TextStyle) -> i32 {
+    (font.cell_height + font.spacing) as i32 * style.scale
+}
+
+/// Size in pixels of `text` drawn by `draw_str_vertical`, excluding the trailing spacing
+pub fn measure_str_vertical(font: &Font, text: &str, style: &TextStyle) -> (i32, i32) {
+    let width = font.cell_width as i32 * style.scale;
+    if text.is_empty() {
+        return (width, 0);
+    }
+    (width, text.chars().count() as i32 * vertical_advance(font, style) - font.spacing as i32 * style.scale)
+}
+
+/// Draws `text` top to bottom with upright glyphs, one per row, e.g. along a vertical
+/// edge. `proportional` and `underline` don't apply. Returns the y coordinate just past
+/// the last glyph.
+pub fn draw_str_vertical(buffer: &mut [u32], font: &Font, text: &str, x: i32, y: i32, buffer_width: u32, style: &TextStyle) -> i32 {
+    let advance = vertical_advance(font, style);
+    let mut glyph_y = y;
+    for c in text.chars() {
+        draw_char(buffer, x, glyph_y, font, c, buffer_width, style);
+        glyph_y = glyph_y.saturating_add(advance);
+    }
+    glyph_y
+}
+
+/// Vertical placement of a run relative to the surrounding text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Baseline {
+    #[default]
+    Normal,
+    /// Smaller, with its bottom dropped below the normal text's baseline
+    Subscript,
+    /// Smaller, with its top level with the normal text's top
+    Superscript,
+}
+
+impl Baseline {
+    /// Scale and y offset for a run placed this way next to text drawn at `scale` in a
+    /// font `cell_height` pixels tall
+    pub const fn placement(self, scale: i32, cell_height: i32) -> (i32, i32) {
+        let small = if scale > 1 { scale / 2 + scale % 2 } else { 1 };
+        match self {
+            Baseline::Normal => (scale, 0),
+            // The small glyph's baseline row sits on the normal descender row
+            Baseline::Subscript => (small, cell_height * scale - (cell_height - 1) * small),
+            Baseline::Superscript => (small, 0),
+        }
+    }
+}
+
+/// One piece of a mixed run for `draw_runs`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextRun<'a> {
+    pub text: &'a str,
+    pub baseline: Baseline,
+}
+
+/// Draws `runs` one after another starting at `(x, y)`, each with `style` but placed and
+/// scaled by its `baseline`, so e.g. "10" then a subscript "px" lines up. Returns the x
+/// coordinate just past the last run.
+pub fn draw_runs(buffer: &mut [u32], font: &Font, runs: &[TextRun], x: i32, y: i32, buffer_width: u32, style: &TextStyle) -> i32 {
+    runs.iter().fold(x, |x, run| {
+        let (scale, dy) = run.baseline.placement(style.scale, font.cell_height as i32);
+        draw_str(buffer, font, run.text, x, y + dy, buffer_width, &TextStyle { scale, ..*style })
+    })
+}
+
+/// Horizontal placement of a line of text within an area
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// The longest prefix of single-line `text` no wider than `max_width`, cut at a
+/// character boundary
+pub fn fit_str<'a>(font: &Font, text: &'a str, max_width: i32, style: &TextStyle) -> &'a str {
+    let mut fitted = "";
+    for (end, _) in text.char_indices().skip(1).chain([(text.len(), ' ')]) {
+        if measure_str(font, &text[..end], style).0 > max_width {
+            break;
+        }
+        fitted = &text[..end];
+    }
+    fitted
+}
+
+/// Draws one line of `text` inside `area` with `align`, vertically centred. Glyphs that
+/// would cross the sides of `area` are dropped from the end. Returns the drawn width.
+pub fn draw_str_aligned(buffer: &mut [u32], font: &Font, text: &str, area: Rect, align: Align, buffer_width: u32, style: &TextStyle) -> i32 {
+    let text = fit_str(font, text, area.width, style);
+    let (width, height) = measure_str(font, text, style);
+    let x = match align {
+        Align::Left => area.x,
+        Align::Center => area.x + (area.width - width) / 2,
+        Align::Right => area.right() - width,
+    };
+    draw_str(buffer, font, text, x, area.y + (area.height - height) / 2, buffer_width, style);
+    width
+}
+
+/// Draws `(text, color)` runs one after another on a single line starting at `(x, y)`,
+/// each with `style` in its own color. Returns the x coordinate just past the last run.
+pub fn draw_rich_text(buffer: &mut [u32], font: &Font, runs: &[(&str, u32)], x: i32, y: i32, buffer_width: u32, style: &TextStyle) -> i32 {
+    runs.iter().fold(x, |x, &(text, color)| draw_str(buffer, font, text, x, y, buffer_width, &TextStyle { color, ..*style }))
+}
+
+/// Lays `rows` out as a two-column table, one row per line, with the left column padded
+/// to its longest entry so the right one lines up in a fixed-width font
+pub fn two_columns(rows: &[(&str, &str)]) -> String {
+    let left_width = rows.iter().map(|(left, _)| left.chars().count()).max().unwrap_or(0);
+    rows.iter()
+        .map(|(left, right)| format!("{left:<left_width$}  {right}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Draws a line `text_width` pixels long just under text whose cells end at `bottom`
+pub fn draw_underline(buffer: &mut [u32], buffer_width: u32, x: i32, bottom: i32, text_width: i32, scale: i32, color: u32) {
+    if text_width <= 0 {
+        return;
+    }
+    let buffer_height = buffer.len() as u32 / buffer_width.max(1);
+    // Clip to the buffer first so text far off screen doesn't walk a huge line
+    let start = x.max(0);
+    let end = saturate(x as i64 + text_width as i64 - 1).min(buffer_width as i32 - 1);
+    if start > end {
+        return;
+    }
+    for row in 0..(scale / 3).max(1) {
+        let line_y = saturate(bottom as i64 + 1 + row as i64);
+        draw_line(buffer, buffer_width, buffer_height, (start, line_y), (end, line_y), color);
+    }
+}
+
+/// Draws the glyph for `c` in `font`, if it has one, with `style.scale` and
+/// `style.color` (or per-pixel contrast with `style.auto_contrast`); `underline` and
+/// `proportional` don't apply to single glyphs
+pub fn draw_char(buffer: &mut [u32], x: i32, y: i32, font: &Font, c: char, buffer_width: u32, style: &TextStyle) {
+    if let Some(glyph) = font.glyph(c) {
+        draw_cell(buffer, x, y, glyph, font.cell_width, buffer_width, style);
+    }
+}
+
+/// Index of the pixel at `(x, y)` in a buffer `buffer_width` wide, `None` left of, right
+/// of or above it. Coordinates are widened so glyphs near the ends of the `i32` range
+/// can't overflow.
+fn pixel_index(x: i64, y: i64, buffer_width: u32) -> Option<usize> {
+    (x >= 0 && y >= 0 && x < buffer_width as i64).then(|| y as usize * buffer_width as usize + x as usize)
+}
+
+/// Draws a row-major glyph `cell_width` pixels wide, like `draw_char`
+fn draw_cell(buffer: &mut [u32], x: i32, y: i32, pixels: &[bool], cell_width: usize, buffer_width: u32, style: &TextStyle) {
+    let scale = style.scale;
+    for (row, line) in pixels.chunks_exact(cell_width).enumerate() {
+        for (col, &pixel) in line.iter().enumerate() {
+            if pixel {
+                // Draw a scale x scale block for each pixel
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        let px = x as i64 + (col as i32 * scale + dx) as i64;
+                        let py = y as i64 + (row as i32 * scale + dy) as i64;
+                        if let Some(pixel) = pixel_index(px, py, buffer_width).and_then(|idx| buffer.get_mut(idx)) {
+                            *pixel = if style.auto_contrast { contrasting(*pixel) } else { style.color };
+                        }
+                    }
+                }
+            }
+        }
+    }
+    if style.aa_diagonals && !style.auto_contrast {
+        smooth_diagonals(buffer, x, y, pixels, cell_width, buffer_width, style);
+    }
+}
+
+/// Most a corner fill covers of its font pixel, out of 255
+const AA_ALPHA: u32 = 128;
+
+/// For every blank font pixel in the notch of a diagonal stair-step (set pixels on two
+/// adjacent sides, a blank one diagonally between them), blends a triangle of
+/// `style.color` into the notch corner, fading out towards the opposite corner. Inside
+/// corners of solid shapes, like that of an L, are left square.
+fn smooth_diagonals(buffer: &mut [u32], x: i32, y: i32, pixels: &[bool], cell_width: usize, buffer_width: u32, style: &TextStyle) {
+    let scale = style.scale;
+    let rows = (pixels.len() / cell_width.max(1)) as i32;
+    let set = |row: i32, col: i32| (0..rows).contains(&row) && (0..cell_width as i32).contains(&col) && pixels[row as usize * cell_width + col as usize];
+    for row in 0..rows {
+        for col in 0..cell_width as i32 {
+            if set(row, col) {
+                continue;
+            }
+            // Each corner as (horizontal neighbour step, vertical neighbour step)
+            for (step_x, step_y) in [(-1, -1), (1, -1), (-1, 1), (1, 1)] {
+                if !set(row, col + step_x) || !set(row + step_y, col) || set(row + step_y, col + step_x) {
+                    continue;
+                }
+                for dy in 0..scale {
+                    for dx in 0..scale {
+                        // Distance in pixels from the notch corner along each axis
+                        let u = if step_x < 0 { dx } else { scale - 1 - dx };
+                        let v = if step_y < 0 { dy } else { scale - 1 - dy };
+                        if u + v >= scale {
+                            continue;
+                        }
+                        let (px, py) = (x as i64 + (col * scale + dx) as i64, y as i64 + (row * scale + dy) as i64);
+                        if let Some(pixel) = pixel_index(px, py, buffer_width).and_then(|idx| buffer.get_mut(idx)) {
+                            let alpha = AA_ALPHA * (scale - u - v) as u32 / scale as u32;
+                            *pixel = blend_pixel(*pixel, style.color, alpha as u8, false);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    /// `text` drawn at `(2, 2)` into a blank 24x16 buffer
+    fn draw(text: &str, style: &TextStyle) -> Vec<u32> {
+        let mut buffer = vec![0; 24 * 16];
+        draw_str(&mut buffer, &Font::builtin(), text, 2, 2, 24, style);
+        buffer
+    }
+
+    fn row(buffer: &[u32], y: usize) -> &[u32] {
+        &buffer[y * 24..(y + 1) * 24]
+    }
+
+    #[test]
+    fn reveal_limit_draws_only_the_first_glyphs() {
+        use crate::font::BAKED_TEXTS;
+        const WIDTH: u32 = 240;
+        let style = TextStyle { scale: 1, ..TextStyle::default() };
+        let text = &BAKED_TEXTS[1];
+        let draw = |reveal_chars| {
+            let mut buffer = vec![0; WIDTH as usize * 8];
+            draw_text(&mut buffer, text, 0, 0, WIDTH, &style, reveal_chars);
+            buffer
+        };
+        let (full, partial) = (draw(None), draw(Some(3)));
+        let split = 3 * ADVANCE as usize;
+        for y in 0..8 {
+            let row = y * WIDTH as usize;
+            // The revealed glyphs sit exactly where they do in the full run
+            assert_eq!(partial[row..row + split], full[row..row + split]);
+            assert!(partial[row + split..row + WIDTH as usize].iter().all(|&pixel| pixel == 0));
+        }
+        assert!(full.iter().skip(split).any(|&pixel| pixel != 0));
+        assert_eq!(draw(Some(1000)), full);
+    }
+
+    #[test]
+    fn auto_contrast_flips_color_per_background() {
+        const BLACK: u32 = 0xFF000000;
+        const GREEN: u32 = 0xFF00FF00;
+        // Black on the left 5 columns, green from there on, under a glyph 10 px wide
+        let mut buffer: Vec<u32> = (0..20 * 16).map(|i| if i % 20 < 5 { BLACK } else { GREEN }).collect();
+        let style = TextStyle { scale: 2, auto_contrast: true, ..TextStyle::default() };
+        draw_str(&mut buffer, &Font::builtin(), "M", 0, 0, 20, &style);
+        let (left, right): (Vec<_>, Vec<_>) = buffer.iter().enumerate().partition(|(i, _)| i % 20 < 5);
+        assert!(left.iter().any(|&(_, &pixel)| pixel == 0xFFFFFFFF));
+        assert!(left.iter().all(|&(_, &pixel)| pixel == BLACK || pixel == 0xFFFFFFFF));
+        assert!(right.iter().any(|&(_, &pixel)| pixel == BLACK));
+        assert!(right.iter().all(|&(_, &pixel)| pixel == GREEN || pixel == BLACK));
+    }
+
+    #[test]
+    fn subscript_run_is_lowered_and_the_next_run_continues_after_it() {
+        let font = Font::builtin();
+        let style = TextStyle { scale: 4, ..TextStyle::default() };
+        assert_eq!(Baseline::Subscript.placement(4, 8), (2, 18));
+        let runs = [
+            TextRun { text: "1", baseline: Baseline::Normal },
+            TextRun { text: "x", baseline: Baseline::Subscript },
+            TextRun { text: "1", baseline: Baseline::Normal },
+        ];
+        let mut mixed = vec![0; 64 * 40];
+        let end = draw_runs(&mut mixed, &font, &runs, 0, 0, 64, &style);
+        assert_eq!(end, 4 * ADVANCE + 2 * ADVANCE + 4 * ADVANCE);
+
+        // The same glyphs drawn one by one where the runs should have put them
+        let mut separate = vec![0; 64 * 40];
+        draw_str(&mut separate, &font, "1", 0, 0, 64, &style);
+        draw_str(&mut separate, &font, "x", 4 * ADVANCE, 18, 64, &TextStyle { scale: 2, ..style });
+        draw_str(&mut separate, &font, "1", 6 * ADVANCE, 0, 64, &style);
+        assert!(mixed == separate);
+    }
+
+    #[test]
+    fn fonts_of_either_cell_size_draw_at_their_own_size() {
+        use std::collections::HashMap;
+        // 8x16 cells, every pixel set, no extra spacing
+        let big = Font::new(8, 16, 0, HashMap::from([('A', vec![true; 8 * 16])]));
+        let mut buffer = vec![0; 20 * 20];
+        let end = draw_str(&mut buffer, &big, "AA", 0, 0, 20, &TextStyle { scale: 1, ..TextStyle::default() });
+        assert_eq!(end, 16);
+        for (i, &pixel) in buffer.iter().enumerate() {
+            assert_eq!(pixel == TEXT_COLOR, i % 20 < 16 && i / 20 < 16, "pixel {i}");
+        }
+
+        // The built-in 5x8 font at scale 2: each font pixel becomes a 2x2 block
+        let builtin = Font::builtin();
+        let glyph = builtin.glyph('A').unwrap();
+        let mut buffer = vec![0; 12 * 16];
+        draw_str(&mut buffer, &builtin, "A", 0, 0, 12, &TextStyle { scale: 2, ..TextStyle::default() });
+        for (i, &pixel) in buffer.iter().enumerate() {
+            let (x, y) = (i % 12 / 2, i / 12 / 2);
+            assert_eq!(pixel == TEXT_COLOR, x < GLYPH_WIDTH && glyph[y * GLYPH_WIDTH + x], "pixel {i}");
+        }
+    }
+
+    #[test]
+    fn shadow_is_offset_and_the_foreground_is_drawn_over_it() {
+        const SHADOW: u32 = 0xFF202020;
+        let plain = TextStyle { scale: 1, ..TextStyle::default() };
+        let foreground = draw("I", &plain);
+        let shadowed = draw("I", &TextStyle { shadow: Some((1, 2, SHADOW)), ..plain });
+        for (i, &pixel) in shadowed.iter().enumerate() {
+            let (x, y) = (i % 24, i / 24);
+            let under = y >= 2 && x >= 1 && foreground[i - 2 * 24 - 1] == TEXT_COLOR;
+            let expected = match (foreground[i] == TEXT_COLOR, under) {
+                (true, _) => TEXT_COLOR,
+                (false, true) => SHADOW,
+                (false, false) => 0,
+            };
+            assert_eq!(pixel, expected, "pixel ({x}, {y})");
+        }
+        assert!(shadowed.contains(&SHADOW));
+
+        // A shadow pushed past the buffer edge is clipped rather than wrapped
+        let mut buffer = vec![0; 24 * 16];
+        draw_str(&mut buffer, &Font::builtin(), "I", 18, 10, 24, &TextStyle { shadow: Some((6, 6, SHADOW)), ..plain });
+        assert!(!buffer.contains(&SHADOW));
+    }
+
+    #[test]
+    fn rich_text_runs_keep_their_colors_and_advance_continuously() {
+        const GREEN: u32 = 0xFF00FF00;
+        const WHITE: u32 = 0xFFFFFFFF;
+        let font = Font::builtin();
+        let style = TextStyle { scale: 1, ..TextStyle::default() };
+        let mut rich = vec![0; 24 * 16];
+        let end = draw_rich_text(&mut rich, &font, &[("{", GREEN), ("A", WHITE), ("}", GREEN)], 2, 2, 24, &style);
+        assert_eq!(end, 2 + 3 * ADVANCE);
+
+        // Each run in its color exactly where one continuous line would put its glyphs
+        let mut separate = vec![0; 24 * 16];
+        for (i, (text, color)) in [("{", GREEN), ("A", WHITE), ("}", GREEN)].into_iter().enumerate() {
+            draw_str(&mut separate, &font, text, 2 + i as i32 * ADVANCE, 2, 24, &TextStyle { color, ..style });
+        }
+        assert!(rich == separate);
+        let columns = |color| (0..24).filter(|&x| (0..16).any(|y| rich[y * 24 + x] == color)).collect::<Vec<_>>();
+        let white = columns(WHITE);
+        assert!(!white.is_empty());
+        assert!(columns(GREEN).iter().all(|x| !white.contains(x)));
+        assert!(rich.iter().all(|&pixel| [0, GREEN, WHITE].contains(&pixel)));
+    }
+
+    #[test]
+    fn snapping_rounds_to_the_nearest_multiple_of_the_scale() {
+        for (coord, snapped) in [(0, 0), (1, 0), (2, 3), (4, 3), (5, 6), (-1, 0), (-2, -3), (-4, -3), (-5, -6)] {
+            assert_eq!(snap_to_scale(coord, 3), snapped, "{coord} at scale 3");
+        }
+        // Exact halves go up, on either side of zero
+        assert_eq!(snap_to_scale(2, 4), 4);
+        assert_eq!(snap_to_scale(-2, 4), 0);
+        assert_eq!(snap_to_scale(-6, 4), -4);
+        // Nothing to snap to at scale 1, and no overflow at the ends of the range
+        assert_eq!(snap_to_scale(-7, 1), -7);
+        assert_eq!(snap_to_scale(i32::MAX, 4), i32::MAX);
+        assert_eq!(snap_to_scale(i32::MIN, 4), i32::MIN);
+    }
+
+    #[test]
+    fn diagonal_strokes_get_partial_alpha_corners() {
+        let font = Font::builtin();
+        let draw_scaled = |c: &str, aa_diagonals| {
+            let mut buffer = vec![0xFF000000; 32 * 40];
+            draw_str(&mut buffer, &font, c, 0, 0, 32, &TextStyle { scale: 4, aa_diagonals, ..TextStyle::default() });
+            buffer
+        };
+        let (plain, smoothed) = (draw_scaled("V", false), draw_scaled("V", true));
+        let intermediate: Vec<_> = smoothed.iter().filter(|&&pixel| pixel != 0xFF000000 && pixel != TEXT_COLOR).collect();
+        assert!(!intermediate.is_empty());
+        // White over black: shades of grey, strongest in the notch corners
+        assert!(intermediate.iter().all(|&&pixel| {
+            let grey = pixel & 0xFF;
+            pixel == 0xFF000000 | (grey * 0x010101) && grey > 0 && grey < 0xFF
+        }));
+        assert!(intermediate.contains(&&(0xFF000000 | (AA_ALPHA * 0x010101))));
+        // Only blank pixels are softened; the glyph itself is untouched
+        for (&before, &after) in plain.iter().zip(&smoothed) {
+            assert!(before == after || before == 0xFF000000, "{before:08X} became {after:08X}");
+        }
+        // No diagonals, nothing to smooth
+        assert!(draw_scaled("L", true) == draw_scaled("L", false));
+    }
+
+    #[test]
+    fn accented_glyphs_resolve_and_missing_ones_draw_tofu() {
+        use crate::font::LATIN1_GLYPHS;
+        const WIDTH: usize = 5 * ADVANCE as usize;
+        let font = Font::builtin();
+        let mut buffer = vec![0; WIDTH * GLYPH_HEIGHT];
+        draw_str(&mut buffer, &font, "café€", 0, 0, WIDTH as u32, &TextStyle { scale: 1, ..TextStyle::default() });
+        // The set pixels of the `index`th cell, row by row
+        let cell = |index: usize| -> Vec<bool> {
+            (0..GLYPH_HEIGHT * GLYPH_WIDTH)
+                .map(|i| buffer[i / GLYPH_WIDTH * WIDTH + index * ADVANCE as usize + i % GLYPH_WIDTH] == TEXT_COLOR)
+                .collect()
+        };
+        let e_acute = LATIN1_GLYPHS.iter().find(|(c, _)| *c == 'é').unwrap().1.as_flattened().to_vec();
+        assert_eq!(font.glyph('é'), Some(&e_acute[..]));
+        assert_eq!(cell(3), e_acute);
+        assert_eq!(cell(0), font.glyph('c').unwrap());
+        // No glyph for the euro sign in the built-in font
+        assert_eq!(font.glyph('€'), None);
+        assert_eq!(cell(4), font.tofu());
+        assert_ne!(cell(3), font.tofu());
+    }
+
+    #[test]
+    fn tofu_is_built_once_for_the_cell_size() {
+        let font = Font::new(4, 4, 0, std::collections::HashMap::new());
+        #[rustfmt::skip]
+        let outline = [
+            true, true, true, true,
+            true, false, false, true,
+            true, true, true, true,
+            false, false, false, false,
+        ];
+        assert_eq!(font.tofu(), outline);
+        assert!(std::ptr::eq(font.tofu(), font.tofu()));
+    }
+
+    #[test]
+    fn line_spacing_moves_each_line_down() {
+        let font = Font::builtin();
+        for line_spacing in [3, 0, -5] {
+            let style = TextStyle { scale: 1, line_spacing, ..TextStyle::default() };
+            let mut lines = vec![0; 8 * 40];
+            draw_str(&mut lines, &font, "A\nB\nC", 0, 0, 8, &style);
+            // Each line where a single-line draw at `i * (8 + line_spacing)` puts it
+            let mut separate = vec![0; 8 * 40];
+            for (i, text) in ["A", "B", "C"].into_iter().enumerate() {
+                draw_str(&mut separate, &font, text, 0, i as i32 * (8 + line_spacing), 8, &style);
+            }
+            assert!(lines == separate, "line spacing {line_spacing}");
+            assert_eq!(measure_str(&font, "A\nB\nC", &style).1, 8 + 2 * (8 + line_spacing));
+        }
+    }
+
+    #[test]
+    fn two_column_table_has_a_line_per_row() {
+        let rows = [("? F1", "show or hide this help"), ("d", "toggle decorations"), ("arrows", "move the window")];
+        let text = two_columns(&rows);
+        assert_eq!(text.lines().count(), rows.len());
+        assert_eq!(text.lines().nth(1), Some("d       toggle decorations"));
+        // The descriptions all start in the same column
+        assert!(text.lines().zip(rows).all(|(line, (_, right))| line.find(right) == Some(8)));
+        assert_eq!(two_columns(&[]), "");
+    }
+
+    #[test]
+    fn kerned_pair_is_closer_than_an_unkerned_one() {
+        let font = Font::builtin();
+        let style = TextStyle { scale: 2, proportional: true, ..TextStyle::default() };
+        assert_eq!(kerning('A', 'V'), -1);
+        assert_eq!(kerning('A', 'X'), 0);
+        let kerned = glyph_offsets(&font, "AV", &style)[1];
+        let unkerned = glyph_offsets(&font, "AX", &style)[1];
+        assert_eq!(kerned, unkerned - style.scale);
+        // Kerning only applies to proportional text
+        let fixed = TextStyle { proportional: false, ..style };
+        assert_eq!(glyph_offsets(&font, "AV", &fixed), glyph_offsets(&font, "AX", &fixed));
+    }
+
+    #[test]
+    fn underline_is_set_just_below_the_glyphs() {
+        let plain = TextStyle { scale: 1, ..TextStyle::default() };
+        let underlined = draw("AB", &TextStyle { underline: true, ..plain });
+        // The cells cover rows 2..10, so the line is on row 11 and spans both glyphs
+        // without the trailing spacing
+        let width = 2 * ADVANCE as usize - 1;
+        assert!(row(&underlined, 11)[2..2 + width].iter().all(|&pixel| pixel == TEXT_COLOR));
+        assert_eq!(row(&underlined, 11).iter().filter(|&&pixel| pixel != 0).count(), width);
+        assert!(row(&draw("AB", &plain), 11).iter().all(|&pixel| pixel == 0));
+        assert_eq!(&underlined[..11 * 24], &draw("AB", &plain)[..11 * 24]);
+    }
+
+    #[test]
+    fn text_far_left_renders_nothing_and_skips_every_glyph() {
+        let font = Font::builtin();
+        let text = "A".repeat(10_000);
+        assert!(visible_glyph_range(&font, -100_000, 0, text.len(), 1, 64, 16).is_empty());
+
+        let mut buffer = vec![0; 64 * 16];
+        let start = Instant::now();
+        let end = draw_str(&mut buffer, &font, &text, -100_000, 0, 64, &TextStyle { scale: 1, ..TextStyle::default() });
+        // Culled glyphs cost an iteration each at most; drawing them would be far slower
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(buffer.iter().all(|&pixel| pixel == 0));
+        assert_eq!(end, -100_000 + 10_000 * ADVANCE);
+    }
+}
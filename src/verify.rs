@@ -0,0 +1,46 @@
+//! Headless self-test that the baked flag renders where it should and reads back intact
+//!
+//! CI runs it once per flag build, e.g.
+//! `cargo run --release --no-default-features --features windowing,flag-b -- --verify-flag`,
+//! and fails the job on a non-zero exit status. No window or display is needed.
+
+use crate::font::{FLAG, GLYPH_HEIGHT, GLYPH_WIDTH, Glyph, glyph_to_byte};
+use crate::render::{BASE_TEXT_SCALE, RenderOptions, hidden_text_rect};
+use crate::snapshot::RenderedFrame;
+use crate::text::ADVANCE;
+
+/// Blank pixels around the text in the headless frame
+const MARGIN: i32 = 8;
+
+/// Renders a frame with a window placed over the hidden text on a monitor of
+/// `monitor_size`, reads the glyphs back out of it and compares them with `FLAG`.
+/// Returns the text read back, or a description of what went wrong.
+pub fn verify_flag(monitor_size: (u32, u32)) -> Result<String, String> {
+    let options = RenderOptions::default();
+    let scale = BASE_TEXT_SCALE;
+    let text = hidden_text_rect(monitor_size, options.hidden_text().source.len(), scale);
+    let size = ((text.width + 2 * MARGIN) as u32, (text.height + 2 * MARGIN) as u32);
+    let frame = RenderedFrame::render(size, (text.x - MARGIN, text.y - MARGIN), monitor_size, &options);
+
+    let mut read = String::new();
+    for i in 0..FLAG.len() as i32 {
+        let mut glyph: Glyph = [[false; GLYPH_WIDTH]; GLYPH_HEIGHT];
+        for (row, line) in glyph.iter_mut().enumerate() {
+            for (col, pixel) in line.iter_mut().enumerate() {
+                // Sample the middle of each scaled font pixel
+                let x = text.x + i * ADVANCE * scale + col as i32 * scale + scale / 2;
+                let y = text.y + row as i32 * scale + scale / 2;
+                *pixel = frame.sample_world_pixel(x, y) == Some(options.text.color);
+            }
+        }
+        match glyph_to_byte(&glyph) {
+            Some(byte) => read.push(byte as char),
+            None => return Err(format!("glyph {i} didn't match any font entry (read {read:?} so far)")),
+        }
+    }
+    if read == FLAG {
+        Ok(read)
+    } else {
+        Err(format!("read back {read:?}, expected {FLAG:?}"))
+    }
+}